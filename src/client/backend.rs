@@ -0,0 +1,149 @@
+use super::cluster;
+use redis::aio::{ConnectionLike, ConnectionManager, ConnectionManagerConfig};
+use redis::cluster::ClusterClient;
+use redis::cluster_async::ClusterConnection;
+use redis::cluster_routing::SingleNodeRoutingInfo;
+use redis::{Client, Cmd, Pipeline, RedisError, RedisFuture, Value};
+
+/// Client for the Redis deployment stashless talks to
+#[derive(Clone)]
+pub enum RedisClient {
+    /// A single server (standalone or the current Sentinel master)
+    Single(Client),
+    /// A Redis Cluster, commands are routed by hash slot
+    Cluster(ClusterClient),
+}
+
+impl RedisClient {
+    /// Open a connection, in a single attempt.
+    ///
+    /// Retries are left to `RedisPool` and SLASHLESS_MAX_RETRY: the manager's
+    /// own backoff waits minutes between attempts, so a rejected password
+    /// would hang startup instead of failing it.
+    pub async fn connect(&self) -> Result<RedisConnection, RedisError> {
+        match self {
+            RedisClient::Single(client) => {
+                let config = ConnectionManagerConfig::new().set_number_of_retries(0);
+                Ok(RedisConnection::Single(Box::new(
+                    client.get_connection_manager_with_config(config).await?,
+                )))
+            }
+            RedisClient::Cluster(client) => Ok(RedisConnection::Cluster(
+                client.get_async_connection().await?,
+            )),
+        }
+    }
+}
+
+/// Multiplexed connection held by the pool
+#[derive(Clone)]
+pub enum RedisConnection {
+    Single(Box<ConnectionManager>),
+    Cluster(ClusterConnection),
+}
+
+/// Raw replies of a transaction, errors kept as values so a failure to
+/// queue a command can be told apart from a failure of the command itself
+pub struct TransactionReplies {
+    /// Replies to the read commands run after WATCH
+    pub reads: Vec<Value>,
+    pub multi: Value,
+    /// `QUEUED` or the queueing error, one per command
+    pub queued: Vec<Value>,
+    /// Array of results, EXECABORT error, or nil when a watched key changed
+    pub exec: Value,
+}
+
+impl RedisConnection {
+    /// Run a MULTI/EXEC block, optionally preceded by WATCH and read commands.
+    ///
+    /// WATCH and the reads are sent first, the block is only sent once WATCH
+    /// succeeded. In a cluster everything goes to the node owning the slot of
+    /// the keys.
+    pub async fn transaction(
+        &mut self,
+        watch: Option<&Cmd>,
+        reads: &[Cmd],
+        cmds: &[Cmd],
+    ) -> Result<TransactionReplies, RedisError> {
+        let route = cluster::transaction_route(watch.into_iter().chain(reads).chain(cmds));
+
+        let mut reads_replies = Vec::new();
+        if watch.is_some() || !reads.is_empty() {
+            let mut pipeline = Pipeline::with_capacity(reads.len() + 1);
+            for cmd in watch.into_iter().chain(reads) {
+                pipeline.add_command(cmd.clone());
+            }
+            reads_replies = self.send(&pipeline, route.clone()).await?;
+
+            // Running the block unwatched would silently drop the guarantee
+            if watch.is_some() {
+                reads_replies.remove(0).extract_error()?;
+            }
+        }
+
+        let mut pipeline = Pipeline::with_capacity(cmds.len() + 2);
+        pipeline.add_command(redis::cmd("MULTI"));
+        for cmd in cmds {
+            pipeline.add_command(cmd.clone());
+        }
+        pipeline.add_command(redis::cmd("EXEC"));
+        let mut replies = self.send(&pipeline, route).await?;
+
+        let exec = replies.pop().unwrap_or(Value::Nil);
+        let multi = replies.remove(0);
+        Ok(TransactionReplies {
+            reads: reads_replies,
+            multi,
+            queued: replies,
+            exec,
+        })
+    }
+
+    /// Send a pipeline and return every raw reply
+    async fn send(
+        &mut self,
+        pipeline: &Pipeline,
+        route: SingleNodeRoutingInfo,
+    ) -> Result<Vec<Value>, RedisError> {
+        let count = pipeline.cmd_iter().count();
+        match self {
+            RedisConnection::Single(conn) => conn.req_packed_commands(pipeline, 0, count).await,
+            RedisConnection::Cluster(conn) => conn.route_pipeline(pipeline, 0, count, route).await,
+        }
+    }
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            RedisConnection::Single(conn) => conn.req_packed_command(cmd),
+            RedisConnection::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            RedisConnection::Single(conn) => conn.req_packed_commands(pipeline, offset, count),
+            // A plain pipeline reads every reply, it can be split across nodes.
+            // Atomic ones (MULTI/EXEC) only read the EXEC reply and must reach a
+            // single node as-is.
+            RedisConnection::Cluster(conn) if offset == 0 => {
+                Box::pin(cluster::execute_pipeline(conn.clone(), pipeline))
+            }
+            RedisConnection::Cluster(conn) => conn.req_packed_commands(pipeline, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            RedisConnection::Single(conn) => conn.get_db(),
+            RedisConnection::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
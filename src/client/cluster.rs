@@ -0,0 +1,124 @@
+use crate::utils::commands::command_keys;
+use futures_util::future::try_join_all;
+use redis::aio::ConnectionLike;
+use redis::cluster_async::ClusterConnection;
+use redis::cluster_routing::{get_slot, Route, SingleNodeRoutingInfo, SlotAddr};
+use redis::{Arg, Cmd, Pipeline, RedisError, Value};
+use std::collections::BTreeMap;
+
+/// Error returned when a transaction touches keys in several hash slots
+pub const CROSSSLOT_ERROR: &str = "CROSSSLOT Keys in request don't hash to the same slot";
+
+/// Hash slot targeted by a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandSlot {
+    /// No key, any node can answer (or the cluster client fans it out)
+    Keyless,
+    /// All keys hash to this slot
+    Single(u16),
+    /// Keys spread over several slots
+    Multiple,
+}
+
+fn command_slot(cmd: &Cmd) -> CommandSlot {
+    let args: Vec<&[u8]> = cmd
+        .args_iter()
+        .filter_map(|arg| match arg {
+            Arg::Simple(arg) => Some(arg),
+            Arg::Cursor => None,
+        })
+        .collect();
+
+    let mut slots = command_keys(&args).into_iter().map(get_slot);
+    match slots.next() {
+        None => CommandSlot::Keyless,
+        Some(slot) if slots.all(|other| other == slot) => CommandSlot::Single(slot),
+        Some(_) => CommandSlot::Multiple,
+    }
+}
+
+/// Whether all keys of the commands hash to the same slot, as required for a
+/// MULTI/EXEC transaction in a cluster
+pub fn same_slot<'a>(cmds: impl IntoIterator<Item = &'a Cmd>) -> bool {
+    let mut target = None;
+    for cmd in cmds {
+        match (command_slot(cmd), target) {
+            (CommandSlot::Keyless, _) => {}
+            (CommandSlot::Multiple, _) => return false,
+            (CommandSlot::Single(slot), None) => target = Some(slot),
+            (CommandSlot::Single(slot), Some(target)) if slot != target => return false,
+            (CommandSlot::Single(_), Some(_)) => {}
+        }
+    }
+    true
+}
+
+/// Node serving a transaction: the owner of its keys' slot, any node when
+/// the transaction has no key
+pub fn transaction_route<'a>(cmds: impl IntoIterator<Item = &'a Cmd>) -> SingleNodeRoutingInfo {
+    let slot = cmds.into_iter().find_map(|cmd| match command_slot(cmd) {
+        CommandSlot::Single(slot) => Some(slot),
+        CommandSlot::Keyless | CommandSlot::Multiple => None,
+    });
+    match slot {
+        Some(slot) => SingleNodeRoutingInfo::SpecificNode(Route::new(slot, SlotAddr::Master)),
+        None => SingleNodeRoutingInfo::Random,
+    }
+}
+
+/// Run a pipeline on a cluster, one sub-pipeline per hash slot.
+///
+/// Commands run in request order wherever it matters. The pipeline is cut
+/// into consecutive runs of single-slot commands: within a run, commands on
+/// different slots touch different keys, so the per-slot sub-pipelines run
+/// concurrently. Commands without keys or with keys in several slots end the
+/// run and are sent on their own, once it has completed, so the cluster
+/// client can route or fan them out. Replies come back in the original order.
+pub async fn execute_pipeline(
+    conn: ClusterConnection,
+    pipeline: &Pipeline,
+) -> Result<Vec<Value>, RedisError> {
+    let cmds: Vec<&Cmd> = pipeline.cmd_iter().collect();
+    let mut results = vec![Value::Nil; cmds.len()];
+
+    let mut run: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+    for (index, cmd) in cmds.iter().enumerate() {
+        match command_slot(cmd) {
+            CommandSlot::Single(slot) => run.entry(slot).or_default().push(index),
+            CommandSlot::Keyless | CommandSlot::Multiple => {
+                execute_run(&conn, &cmds, std::mem::take(&mut run), &mut results).await?;
+                let mut conn = conn.clone();
+                results[index] = conn.req_packed_command(cmd).await?;
+            }
+        }
+    }
+    execute_run(&conn, &cmds, run, &mut results).await?;
+
+    Ok(results)
+}
+
+/// Run the per-slot sub-pipelines of a run concurrently, storing the replies
+/// at the index of their command
+async fn execute_run(
+    conn: &ClusterConnection,
+    cmds: &[&Cmd],
+    run: BTreeMap<u16, Vec<usize>>,
+    results: &mut [Value],
+) -> Result<(), RedisError> {
+    let batches = run.into_values().map(|indexes| {
+        let mut conn = conn.clone();
+        let mut batch = Pipeline::with_capacity(indexes.len());
+        for &index in &indexes {
+            batch.add_command(cmds[index].clone());
+        }
+        async move {
+            let values = conn.req_packed_commands(&batch, 0, indexes.len()).await?;
+            Ok::<_, RedisError>(indexes.into_iter().zip(values).collect::<Vec<_>>())
+        }
+    });
+
+    for (index, value) in try_join_all(batches).await?.into_iter().flatten() {
+        results[index] = value;
+    }
+    Ok(())
+}
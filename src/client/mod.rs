@@ -1,21 +1,69 @@
-use crate::config::Config;
+mod backend;
+mod cluster;
+mod pool;
+mod pubsub;
+mod replicas;
+mod scripts;
+mod sentinel;
+
+use crate::config::{Config, RedisTlsConfig};
 use crate::console::Console;
-use redis::aio::ConnectionManager;
-use redis::{Client, RedisError};
-use std::sync::{
-    atomic::{AtomicU32, Ordering},
-    Arc,
+use crate::utils::commands::is_read_only;
+use crate::utils::metrics;
+use redis::aio::ConnectionLike;
+use redis::cluster::ClusterClient;
+use redis::{
+    Client, ClientTlsConfig, ConnectionAddr, ConnectionInfo, IntoConnectionInfo, ProtocolVersion,
+    RedisError, TlsCertificates, TlsMode,
 };
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Semaphore;
 use tokio::time::sleep;
 
+pub use backend::{RedisClient, TransactionReplies};
+pub use cluster::{same_slot, CROSSSLOT_ERROR};
+pub use pool::{ConnectionPool, PoolStats, PooledConnection};
+pub use pubsub::{PubSubHub, Subscription, Topic};
+pub use replicas::ReplicaSet;
+pub use scripts::ScriptRegistry;
+
+/// Where a request is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Primary,
+    /// A read replica, or the primary when none is configured
+    Replica,
+    /// The primary, over a connection speaking RESP3
+    Resp3,
+}
+
+impl Target {
+    /// Target of a retry: replica reads fall back to the primary in case the
+    /// replica is the one failing
+    fn retry(self) -> Self {
+        match self {
+            Target::Replica => Target::Primary,
+            target => target,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RedisPool {
-    client: Arc<Client>,
-    semaphore: Arc<Semaphore>,
+    connections: ConnectionPool,
+    /// Connections to the primary negotiating RESP3, only opened for requests
+    /// asking for RESP3 replies
+    resp3: ConnectionPool,
     console: Option<Arc<Console>>,
     max_retry: i32,
+    /// Set while Redis is unreachable, until a retry succeeds
+    reconnecting: Arc<AtomicBool>,
+    /// Client for the first cluster seed node, Pub/Sub messages are broadcast
+    /// to the whole cluster so any node can serve subscriptions
+    cluster_seed: Option<Client>,
+    replicas: Option<ReplicaSet>,
+    scripts: ScriptRegistry,
 }
 
 impl RedisPool {
@@ -28,23 +76,112 @@ impl RedisPool {
         config: &Config,
         console: Option<Arc<Console>>,
     ) -> Result<Self, RedisError> {
-        let url = config.redis_url();
+        let mut cluster_seed = None;
+        let (client, resp3_client, master) = match &config.redis_cluster_nodes {
+            Some(nodes) => {
+                let seed = node_connection_info(&config.redis_connection_info()?, &nodes[0])?;
+                cluster_seed = Some(build_client(seed, &config.redis_tls)?);
+                (
+                    RedisClient::Cluster(build_cluster_client(
+                        config,
+                        nodes,
+                        ProtocolVersion::RESP2,
+                    )?),
+                    RedisClient::Cluster(build_cluster_client(
+                        config,
+                        nodes,
+                        ProtocolVersion::RESP3,
+                    )?),
+                    None,
+                )
+            }
+            None => {
+                // In sentinel mode the master address is only known at runtime
+                let info = match &config.redis_sentinel {
+                    Some(sentinel) => sentinel::discover_master(config, sentinel).await?,
+                    None => config.redis_connection_info()?,
+                };
 
-        // Configure client with connection timeouts
-        let client = Client::open(url)?;
+                // Configure client with connection timeouts
+                let client = build_client(info.clone(), &config.redis_tls)?;
+                let resp3_client = RedisClient::Single(resp3_client(&client)?);
+                (RedisClient::Single(client), resp3_client, Some(info))
+            }
+        };
+
+        // Test connection and keep it as the first pooled connection
+        let connections = ConnectionPool::new(client, config.max_connections);
+        connections.warm_up().await?;
+        let (mut conn, _) = connections.get().await?;
+        redis::cmd("PING").query_async::<String>(&mut conn).await?;
+        drop(conn);
 
-        // Test connection
-        let mut conn = client.get_connection_manager().await?;
-        redis::cmd("PING")
-            .query_async::<_, String>(&mut conn)
-            .await?;
+        let replicas = config
+            .redis_replicas
+            .as_deref()
+            .map(|endpoints| ReplicaSet::new(config, endpoints))
+            .transpose()?;
 
-        Ok(Self {
-            client: Arc::new(client),
-            semaphore: Arc::new(Semaphore::new(config.max_connections)),
+        let pool = Self {
+            connections,
+            resp3: ConnectionPool::new(resp3_client, config.max_connections),
             console,
             max_retry: config.max_retry,
-        })
+            reconnecting: Arc::default(),
+            cluster_seed,
+            replicas,
+            scripts: ScriptRegistry::default(),
+        };
+
+        if let (Some(console), Some(endpoints)) = (&pool.console, &config.redis_replicas) {
+            let _ = console.log_info(format!(
+                "Routing read-only commands to {} replica(s): {}",
+                endpoints.len(),
+                endpoints.join(", ")
+            ));
+        }
+
+        if let (Some(sentinel), Some(master)) = (&config.redis_sentinel, master) {
+            pool.log_active_master(&master, &sentinel.master_name);
+            sentinel::spawn_master_watcher(pool.clone(), config.clone(), sentinel.clone(), master);
+        }
+
+        Ok(pool)
+    }
+
+    /// Move the pool to a new master after a sentinel failover
+    fn switch_master(&self, client: Client, info: &ConnectionInfo, master_name: &str) {
+        match resp3_client(&client) {
+            Ok(resp3) => self.resp3.reset(RedisClient::Single(resp3)),
+            Err(e) => {
+                if let Some(console) = &self.console {
+                    let _ = console.log_warn(format!("Failed to build the RESP3 client: {}", e));
+                }
+            }
+        }
+        self.connections.reset(RedisClient::Single(client));
+        if let Some(console) = &self.console {
+            let _ = console.log_warn(format!(
+                "Sentinel failover: master {} moved to {}",
+                master_name, info.addr
+            ));
+        }
+        self.log_active_master(info, master_name);
+    }
+
+    /// Show which node currently serves as master
+    fn log_active_master(&self, info: &ConnectionInfo, master_name: &str) {
+        if let Some(console) = &self.console {
+            let address = format!("{} (sentinel master {})", info.addr, master_name);
+            let _ = console.log_info(format!("Redis master is {}", address));
+            let _ = console.update_redis_address(address);
+        }
+    }
+
+    /// Check if Redis rejected our credentials (or requires some we did not send)
+    pub fn is_auth_error(error: &RedisError) -> bool {
+        matches!(error.kind(), redis::ErrorKind::AuthenticationFailed)
+            || matches!(error.code(), Some("NOAUTH") | Some("WRONGPASS"))
     }
 
     /// Check if an error indicates a connection failure that requires reconnection
@@ -66,6 +203,7 @@ impl RedisPool {
 
     /// Log disconnection and update console status
     fn log_disconnection(&self, error: &str) {
+        self.reconnecting.store(true, Ordering::Relaxed);
         if let Some(console) = &self.console {
             let _ = console.log_warn(format!("Redis connection lost: {}", error));
             if let Err(e) = console.update_redis_status(crate::console::Status::Reconnecting) {
@@ -84,6 +222,20 @@ impl RedisPool {
         }
     }
 
+    /// Log a Pub/Sub connection event
+    fn log_pubsub_info(&self, message: String) {
+        if let Some(console) = &self.console {
+            let _ = console.log_info(message);
+        }
+    }
+
+    /// Log a Pub/Sub connection problem
+    fn log_pubsub_warn(&self, message: String) {
+        if let Some(console) = &self.console {
+            let _ = console.log_warn(message);
+        }
+    }
+
     /// Log connection attempt
     fn log_connection_attempt(&self, attempt: u32) {
         if let Some(console) = &self.console {
@@ -100,6 +252,8 @@ impl RedisPool {
 
     /// Log successful reconnection and update console status
     fn log_reconnection_success(&self) {
+        self.reconnecting.store(false, Ordering::Relaxed);
+        metrics::record_reconnection();
         if let Some(console) = &self.console {
             let _ = console.log_info("Redis connection restored".to_string());
             if let Err(e) = console.update_redis_status(crate::console::Status::Connected) {
@@ -123,7 +277,7 @@ impl RedisPool {
         loop {
             match self.get_connection().await {
                 Ok(mut conn) => {
-                    match redis::cmd("PING").query_async::<_, String>(&mut conn).await {
+                    match redis::cmd("PING").query_async::<String>(&mut conn).await {
                         Ok(result) => {
                             // Success on retry - log reconnection
                             if attempt > 1 {
@@ -188,27 +342,148 @@ impl RedisPool {
         }
     }
 
+    /// Whether commands are routed across a Redis Cluster
+    pub fn is_cluster(&self) -> bool {
+        self.cluster_seed.is_some()
+    }
+
+    /// Client for a dedicated Pub/Sub connection, following sentinel failovers
+    pub fn pubsub_client(&self) -> Client {
+        match self.connections.client() {
+            RedisClient::Single(client) => client,
+            RedisClient::Cluster(_) => self
+                .cluster_seed
+                .clone()
+                .expect("cluster pool without a seed client"),
+        }
+    }
+
     /// Get the maximum number of retries (-1 for infinite)
     pub fn max_retry(&self) -> i32 {
         self.max_retry
     }
 
-    pub async fn get_connection(&self) -> Result<ConnectionManager, RedisError> {
-        // Acquire permit from semaphore to limit concurrent connections
-        let _permit = self.semaphore.acquire().await.map_err(|_| {
-            RedisError::from((
-                redis::ErrorKind::IoError,
-                "Failed to acquire connection permit",
-            ))
-        })?;
-
-        match self.client.get_connection_manager().await {
-            Ok(conn) => {
-                // Log to console occasionally to show activity (every 10th connection)
-                static CONNECTION_COUNT: AtomicU32 = AtomicU32::new(0);
-                let count = CONNECTION_COUNT.fetch_add(1, Ordering::Relaxed);
-                if count.is_multiple_of(10) && count > 0 {
+    /// Whether the last request failed to reach Redis and retries are ongoing
+    pub fn is_reconnecting(&self) -> bool {
+        self.reconnecting.load(Ordering::Relaxed)
+    }
+
+    /// Close every pooled connection and check that Redis answers on a new
+    /// one, e.g. after changing firewall rules or DNS records
+    pub async fn reconnect(&self) -> Result<String, RedisError> {
+        self.connections.reconnect();
+        self.resp3.reconnect();
+        if let Some(replicas) = &self.replicas {
+            replicas.reconnect();
+        }
+
+        let pong = self.ping_with_retry().await?;
+        if self.is_reconnecting() {
+            self.log_reconnection_success();
+        }
+        Ok(pong)
+    }
+
+    /// Current usage of the connection pool
+    pub fn stats(&self) -> PoolStats {
+        self.connections.stats()
+    }
+
+    /// Target for a batch of commands: replicas only serve batches made of
+    /// read-only commands, unless the client asked for primary reads
+    pub fn read_target<'a>(
+        &self,
+        cmds: impl IntoIterator<Item = &'a redis::Cmd>,
+        read_primary: bool,
+    ) -> Target {
+        if read_primary || self.replicas.is_none() {
+            return Target::Primary;
+        }
+
+        let read_only = cmds.into_iter().all(|cmd| {
+            cmd.args_iter()
+                .next()
+                .and_then(|name| match name {
+                    redis::Arg::Simple(name) => std::str::from_utf8(name).ok(),
+                    redis::Arg::Cursor => None,
+                })
+                .is_some_and(is_read_only)
+        });
+        if read_only {
+            Target::Replica
+        } else {
+            Target::Primary
+        }
+    }
+
+    /// Prepare the commands of a pipeline or transaction: remember the Lua
+    /// scripts they send and replace EVALSHA of known scripts by EVAL.
+    ///
+    /// A NOSCRIPT error in the middle of a batch cannot be retried without
+    /// running the other commands twice, so the source is always sent.
+    pub fn prepare_batch<'a>(
+        &self,
+        cmds: impl IntoIterator<Item = &'a redis::Cmd>,
+    ) -> Vec<redis::Cmd> {
+        cmds.into_iter()
+            .map(|cmd| {
+                self.scripts.observe(cmd);
+                self.scripts
+                    .eval_fallback(cmd)
+                    .unwrap_or_else(|| cmd.clone())
+            })
+            .collect()
+    }
+
+    /// SHA1 of the Lua scripts known to the proxy
+    pub fn script_shas(&self) -> Vec<String> {
+        self.scripts.shas()
+    }
+
+    /// Load the known scripts on a new connection, the server may have lost
+    /// its script cache (restart, failover)
+    async fn load_scripts(&self, conn: &mut PooledConnection) {
+        let Some(pipeline) = self.scripts.load_pipeline() else {
+            return;
+        };
+        if let Err(e) = pipeline.query_async::<()>(conn).await {
+            if let Some(console) = &self.console {
+                let _ = console.log_warn(format!("Failed to preload Lua scripts: {}", e));
+            }
+        }
+    }
+
+    /// Load the known scripts again in the background after a NOSCRIPT error.
+    ///
+    /// Connections reconnect on their own without going through `checkout`,
+    /// so a server that lost its script cache is only noticed here.
+    fn reload_scripts(&self, target: Target) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            if let Ok(mut conn) = pool.checkout(target).await {
+                pool.load_scripts(&mut conn).await;
+            }
+        });
+    }
+
+    /// Check out a pooled connection, held exclusively until it is dropped
+    pub async fn get_connection(&self) -> Result<PooledConnection, RedisError> {
+        self.checkout(Target::Primary).await
+    }
+
+    /// Check out a pooled connection for a target
+    pub async fn checkout(&self, target: Target) -> Result<PooledConnection, RedisError> {
+        let connections = match (target, &self.replicas) {
+            (Target::Replica, Some(replicas)) => replicas.pick(),
+            (Target::Resp3, _) => &self.resp3,
+            _ => &self.connections,
+        };
+
+        match connections.get().await {
+            Ok((mut conn, opened)) => {
+                if opened {
                     self.log_connection_success();
+                    self.load_scripts(&mut conn).await;
                 }
                 Ok(conn)
             }
@@ -222,10 +497,49 @@ impl RedisPool {
         }
     }
 
+    /// Execute a command, running EVALSHA of a known script again as EVAL
+    /// if the server does not have it cached
     pub async fn execute_command<T: redis::FromRedisValue>(
         &self,
         cmd: redis::Cmd,
+        target: Target,
     ) -> Result<T, RedisError> {
+        let value = self.execute_command_raw(cmd, target).await?;
+        redis::from_owned_redis_value(value.extract_error()?)
+    }
+
+    /// Same as `execute_command`, error replies are returned as values
+    pub async fn execute_command_raw(
+        &self,
+        cmd: redis::Cmd,
+        target: Target,
+    ) -> Result<redis::Value, RedisError> {
+        self.scripts.observe(&cmd);
+        let result = match self.execute_command_with_retry(&cmd, target).await {
+            Ok(redis::Value::ServerError(e)) if e.code() == "NOSCRIPT" => {
+                match self.scripts.eval_fallback(&cmd) {
+                    Some(eval) => {
+                        self.reload_scripts(target);
+                        self.execute_command_with_retry(&eval, target).await
+                    }
+                    None => Ok(redis::Value::ServerError(e)),
+                }
+            }
+            result => result,
+        };
+
+        match &result {
+            Ok(value) => metrics::record_command(&cmd, value),
+            Err(e) => metrics::record_error(e),
+        }
+        result
+    }
+
+    async fn execute_command_with_retry(
+        &self,
+        cmd: &redis::Cmd,
+        target: Target,
+    ) -> Result<redis::Value, RedisError> {
         const RETRY_DELAY: Duration = Duration::from_secs(1);
 
         let mut attempt = 1u32;
@@ -235,9 +549,10 @@ impl RedisPool {
                 self.log_connection_attempt(attempt);
             }
 
-            match self.get_connection().await {
+            let target = if attempt == 1 { target } else { target.retry() };
+            match self.checkout(target).await {
                 Ok(mut conn) => {
-                    match cmd.query_async(&mut conn).await {
+                    match conn.req_packed_command(cmd).await {
                         Ok(result) => {
                             // If we had retried, log success
                             if attempt > 1 {
@@ -254,6 +569,7 @@ impl RedisPool {
 
                                 if self.should_retry(attempt) {
                                     attempt += 1;
+                                    metrics::record_retry("command");
                                     self.log_reconnection_attempt(attempt);
                                     sleep(RETRY_DELAY * (attempt - 1)).await;
                                     continue;
@@ -277,6 +593,7 @@ impl RedisPool {
 
                         if self.should_retry(attempt) {
                             attempt += 1;
+                            metrics::record_retry("command");
                             self.log_reconnection_attempt(attempt);
                             sleep(RETRY_DELAY * (attempt - 1)).await;
                             continue;
@@ -292,9 +609,48 @@ impl RedisPool {
         }
     }
 
+    /// Execute a pipeline, with EVALSHA of known scripts sent as EVAL
     pub async fn execute_pipeline(
         &self,
         pipeline: &mut redis::Pipeline,
+        target: Target,
+    ) -> Result<Vec<redis::Value>, RedisError> {
+        let values = self.execute_pipeline_raw(pipeline, target).await?;
+        match redis::Value::Array(values).extract_error()? {
+            redis::Value::Array(values) => Ok(values),
+            _ => unreachable!("extract_error keeps the value type"),
+        }
+    }
+
+    /// Same as `execute_pipeline`, error replies are returned as values
+    pub async fn execute_pipeline_raw(
+        &self,
+        pipeline: &mut redis::Pipeline,
+        target: Target,
+    ) -> Result<Vec<redis::Value>, RedisError> {
+        let mut prepared = redis::Pipeline::new();
+        for cmd in self.prepare_batch(pipeline.cmd_iter()) {
+            prepared.add_command(cmd);
+        }
+        let result = self
+            .execute_pipeline_with_retry(&mut prepared, target)
+            .await;
+
+        match &result {
+            Ok(values) => {
+                for (cmd, value) in prepared.cmd_iter().zip(values) {
+                    metrics::record_command(cmd, value);
+                }
+            }
+            Err(e) => metrics::record_error(e),
+        }
+        result
+    }
+
+    async fn execute_pipeline_with_retry(
+        &self,
+        pipeline: &mut redis::Pipeline,
+        target: Target,
     ) -> Result<Vec<redis::Value>, RedisError> {
         const RETRY_DELAY: Duration = Duration::from_secs(1);
 
@@ -305,9 +661,11 @@ impl RedisPool {
                 self.log_connection_attempt(attempt);
             }
 
-            match self.get_connection().await {
+            let target = if attempt == 1 { target } else { target.retry() };
+            match self.checkout(target).await {
                 Ok(mut conn) => {
-                    match pipeline.query_async(&mut conn).await {
+                    let count = pipeline.cmd_iter().count();
+                    match conn.req_packed_commands(pipeline, 0, count).await {
                         Ok(result) => {
                             // If we had retried, log success
                             if attempt > 1 {
@@ -324,6 +682,7 @@ impl RedisPool {
 
                                 if self.should_retry(attempt) {
                                     attempt += 1;
+                                    metrics::record_retry("pipeline");
                                     self.log_reconnection_attempt(attempt);
                                     sleep(RETRY_DELAY * (attempt - 1)).await;
                                     continue;
@@ -347,6 +706,7 @@ impl RedisPool {
 
                         if self.should_retry(attempt) {
                             attempt += 1;
+                            metrics::record_retry("pipeline");
                             self.log_reconnection_attempt(attempt);
                             sleep(RETRY_DELAY * (attempt - 1)).await;
                             continue;
@@ -362,3 +722,129 @@ impl RedisPool {
         }
     }
 }
+
+/// Build the Redis client, loading custom TLS certificates when configured
+fn build_client(info: ConnectionInfo, tls: &RedisTlsConfig) -> Result<Client, RedisError> {
+    match tls_certificates(tls)? {
+        Some(certificates) => Client::build_with_tls(info, certificates),
+        None => Client::open(info),
+    }
+}
+
+/// Custom TLS certificates from the configured files, if any
+fn tls_certificates(tls: &RedisTlsConfig) -> Result<Option<TlsCertificates>, RedisError> {
+    if !tls.has_certificates() {
+        return Ok(None);
+    }
+
+    let client_tls = match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => Some(ClientTlsConfig {
+            client_cert: read_tls_file(cert)?,
+            client_key: read_tls_file(key)?,
+        }),
+        _ => None,
+    };
+    let root_cert = tls.ca_cert.as_deref().map(read_tls_file).transpose()?;
+    Ok(Some(TlsCertificates {
+        client_tls,
+        root_cert,
+    }))
+}
+
+/// Same client as `client`, negotiating RESP3 on connect.
+///
+/// `build_with_tls` stores the certificates in the `tls_params` of the
+/// connection address, so they are kept by the cloned connection info.
+fn resp3_client(client: &Client) -> Result<Client, RedisError> {
+    let mut info = client.get_connection_info().clone();
+    info.redis.protocol = ProtocolVersion::RESP3;
+    Client::open(info)
+}
+
+/// Build the cluster client from the seed nodes, sharing credentials and TLS
+/// settings with the single-node mode
+fn build_cluster_client(
+    config: &Config,
+    nodes: &[String],
+    protocol: ProtocolVersion,
+) -> Result<ClusterClient, RedisError> {
+    let tls = &config.redis_tls;
+    let scheme = if tls.enabled { "rediss" } else { "redis" };
+    let nodes: Vec<String> = nodes
+        .iter()
+        .map(|node| {
+            if node.contains("://") {
+                node.clone()
+            } else {
+                format!("{}://{}", scheme, node)
+            }
+        })
+        .collect();
+
+    let redis = config.redis_connection_info()?.redis;
+    let mut builder = ClusterClient::builder(nodes).use_protocol(protocol);
+    if let Some(username) = redis.username {
+        builder = builder.username(username);
+    }
+    if let Some(password) = redis.password {
+        builder = builder.password(password);
+    }
+    if tls.insecure {
+        builder = builder.tls(TlsMode::Insecure);
+    }
+    if let Some(certificates) = tls_certificates(tls)? {
+        builder = builder.certs(certificates);
+    }
+    builder.build()
+}
+
+/// A node (replica, cluster seed) is either a full URL or a `host:port`
+/// reusing the primary credentials, database and TLS settings
+fn node_connection_info(
+    primary: &ConnectionInfo,
+    endpoint: &str,
+) -> Result<ConnectionInfo, RedisError> {
+    if endpoint.contains("://") {
+        return endpoint.into_connection_info();
+    }
+
+    let invalid = || {
+        RedisError::from((
+            redis::ErrorKind::InvalidClientConfig,
+            "Invalid node address, expected host:port",
+            endpoint.to_string(),
+        ))
+    };
+    let (host, port) = endpoint.rsplit_once(':').ok_or_else(invalid)?;
+    let port = port.parse::<u16>().map_err(|_| invalid())?;
+    let host = host.to_string();
+
+    let addr = match &primary.addr {
+        ConnectionAddr::TcpTls {
+            insecure,
+            tls_params,
+            ..
+        } => ConnectionAddr::TcpTls {
+            host,
+            port,
+            insecure: *insecure,
+            tls_params: tls_params.clone(),
+        },
+        _ => ConnectionAddr::Tcp(host, port),
+    };
+
+    Ok(ConnectionInfo {
+        addr,
+        redis: primary.redis.clone(),
+    })
+}
+
+fn read_tls_file(path: &str) -> Result<Vec<u8>, RedisError> {
+    std::fs::read(path).map_err(|e| {
+        RedisError::from((
+            redis::ErrorKind::InvalidClientConfig,
+            "Failed to read TLS file",
+            format!("{}: {}", path, e),
+        ))
+    })
+}
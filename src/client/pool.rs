@@ -0,0 +1,239 @@
+use super::backend::{RedisClient, RedisConnection, TransactionReplies};
+use redis::aio::ConnectionLike;
+use redis::{Cmd, Pipeline, RedisError, RedisFuture, Value};
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Snapshot of the connection pool usage
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PoolStats {
+    /// Maximum number of connections (SLASHLESS_MAX_CONNECTION)
+    pub size: usize,
+    /// Connections currently opened to Redis
+    pub open: usize,
+    /// Opened connections waiting to be checked out
+    pub idle: usize,
+    /// Connections checked out by a request
+    pub in_use: usize,
+    /// Requests waiting for a connection to become available
+    pub waiters: usize,
+}
+
+struct PoolInner {
+    client: Mutex<RedisClient>,
+    /// Bumped when the pool is pointed at another server, older connections
+    /// are closed instead of being returned to the pool
+    generation: AtomicU64,
+    idle: Mutex<Vec<IdleConnection>>,
+    semaphore: Arc<Semaphore>,
+    size: usize,
+    open: AtomicUsize,
+    waiters: AtomicUsize,
+}
+
+struct IdleConnection {
+    generation: u64,
+    conn: RedisConnection,
+}
+
+/// Fixed-size pool of long-lived multiplexed Redis connections.
+///
+/// Connections are opened lazily up to `size` and returned to the pool when the
+/// `PooledConnection` is dropped. Each checked out connection holds a semaphore
+/// permit, so at most `size` requests talk to Redis at the same time.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    inner: Arc<PoolInner>,
+}
+
+impl ConnectionPool {
+    pub fn new(client: RedisClient, size: usize) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                client: Mutex::new(client),
+                generation: AtomicU64::new(0),
+                idle: Mutex::new(Vec::with_capacity(size)),
+                semaphore: Arc::new(Semaphore::new(size)),
+                size,
+                open: AtomicUsize::new(0),
+                waiters: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Open a connection and park it in the pool without checking it out
+    pub async fn warm_up(&self) -> Result<(), RedisError> {
+        let idle = self.open_connection().await?;
+        self.inner.idle.lock().unwrap().push(idle);
+        Ok(())
+    }
+
+    /// Point the pool at a new server (e.g. after a failover).
+    ///
+    /// Idle connections are closed right away, connections in use are closed
+    /// when they are released.
+    pub fn reset(&self, client: RedisClient) {
+        *self.inner.client.lock().unwrap() = client;
+        self.inner.generation.fetch_add(1, Ordering::SeqCst);
+        let closed = std::mem::take(&mut *self.inner.idle.lock().unwrap()).len();
+        self.inner.open.fetch_sub(closed, Ordering::Relaxed);
+    }
+
+    /// Close every connection, new ones are opened on the next checkout
+    pub fn reconnect(&self) {
+        self.reset(self.client());
+    }
+
+    /// Client the pool currently opens connections with
+    pub fn client(&self) -> RedisClient {
+        self.inner.client.lock().unwrap().clone()
+    }
+
+    /// Check out a connection, waiting for a free slot if the pool is exhausted.
+    ///
+    /// Returns the connection and whether it was freshly opened.
+    pub async fn get(&self) -> Result<(PooledConnection, bool), RedisError> {
+        let permit = {
+            let _waiter = WaiterGuard::new(&self.inner.waiters);
+            self.inner
+                .semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .map_err(|_| {
+                    RedisError::from((
+                        redis::ErrorKind::IoError,
+                        "Failed to acquire connection permit",
+                    ))
+                })?
+        };
+
+        let idle = self.inner.idle.lock().unwrap().pop();
+        let (idle, opened) = match idle {
+            Some(idle) => (idle, false),
+            None => (self.open_connection().await?, true),
+        };
+
+        Ok((
+            PooledConnection {
+                conn: Some(idle.conn),
+                generation: idle.generation,
+                discard: false,
+                pool: self.inner.clone(),
+                _permit: permit,
+            },
+            opened,
+        ))
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.inner.size,
+            open: self.inner.open.load(Ordering::Relaxed),
+            idle: self.inner.idle.lock().unwrap().len(),
+            in_use: self.inner.size - self.inner.semaphore.available_permits(),
+            waiters: self.inner.waiters.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn open_connection(&self) -> Result<IdleConnection, RedisError> {
+        let generation = self.inner.generation.load(Ordering::SeqCst);
+        let client = self.client();
+        let conn = client.connect().await?;
+        self.inner.open.fetch_add(1, Ordering::Relaxed);
+        Ok(IdleConnection { generation, conn })
+    }
+}
+
+/// Connection checked out from the pool, returned to it on drop
+pub struct PooledConnection {
+    conn: Option<RedisConnection>,
+    generation: u64,
+    /// Close the connection on release instead of returning it to the pool
+    discard: bool,
+    pool: Arc<PoolInner>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl PooledConnection {
+    fn conn(&mut self) -> &mut RedisConnection {
+        self.conn
+            .as_mut()
+            .expect("pooled connection used after release")
+    }
+
+    /// Run a MULTI/EXEC block on this connection, see
+    /// `RedisConnection::transaction`.
+    ///
+    /// If the transaction does not complete (error or cancelled request), the
+    /// connection may still have keys watched or a MULTI open, so it is closed
+    /// instead of going back to the pool.
+    pub async fn transaction(
+        &mut self,
+        watch: Option<&Cmd>,
+        reads: &[Cmd],
+        cmds: &[Cmd],
+    ) -> Result<TransactionReplies, RedisError> {
+        self.discard = true;
+        let replies = self.conn().transaction(watch, reads, cmds).await?;
+        self.discard = false;
+        Ok(replies)
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        // Push the connection back before the permit is released so the next
+        // waiter finds it idle instead of opening a new one
+        if let Some(conn) = self.conn.take() {
+            let mut idle = self.pool.idle.lock().unwrap();
+            if !self.discard && self.generation == self.pool.generation.load(Ordering::SeqCst) {
+                idle.push(IdleConnection {
+                    generation: self.generation,
+                    conn,
+                });
+            } else {
+                self.pool.open.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl ConnectionLike for PooledConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.conn().req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        self.conn().req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.conn.as_ref().map(|conn| conn.get_db()).unwrap_or(0)
+    }
+}
+
+/// Keeps the waiter count accurate even if the waiting request is cancelled
+struct WaiterGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaiterGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,245 @@
+use super::RedisPool;
+use futures_util::StreamExt;
+use redis::aio::{PubSubSink, PubSubStream};
+use redis::{Msg, RedisError};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::sleep;
+
+/// Messages buffered per topic before slow subscribers start missing some
+const TOPIC_CAPACITY: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// What a subscriber listens to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Channel(String),
+    Pattern(String),
+}
+
+/// Message received on a subscribed topic
+#[derive(Debug, Clone)]
+pub struct PubSubMessage {
+    /// Pattern that matched, for pattern subscriptions
+    pub pattern: Option<String>,
+    pub channel: String,
+    pub payload: Vec<u8>,
+}
+
+struct HubState {
+    /// Sink of the dedicated connection, `None` while (re)connecting
+    sink: Option<PubSubSink>,
+    /// Whether the task owning the connection is running
+    running: bool,
+    topics: HashMap<Topic, broadcast::Sender<PubSubMessage>>,
+}
+
+struct HubInner {
+    pool: RedisPool,
+    state: Mutex<HubState>,
+}
+
+/// Shares one dedicated Pub/Sub connection between all HTTP subscribers.
+///
+/// Each topic is subscribed once in Redis, no matter how many clients listen
+/// to it, and unsubscribed when its last client goes away. The connection is
+/// opened on the first subscription and re-established (with all current
+/// subscriptions) if it drops.
+#[derive(Clone)]
+pub struct PubSubHub {
+    inner: Arc<HubInner>,
+}
+
+impl PubSubHub {
+    pub fn new(pool: RedisPool) -> Self {
+        Self {
+            inner: Arc::new(HubInner {
+                pool,
+                state: Mutex::new(HubState {
+                    sink: None,
+                    running: false,
+                    topics: HashMap::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Start listening to a topic, until the returned `Subscription` is dropped
+    pub async fn subscribe(&self, topic: Topic) -> Subscription {
+        let mut state = self.inner.state.lock().await;
+
+        let receiver = match state.topics.get(&topic) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(TOPIC_CAPACITY);
+                state.topics.insert(topic.clone(), sender);
+
+                // Without a connection, the subscription is made once connected
+                if let Some(sink) = state.sink.as_mut() {
+                    if let Err(e) = subscribe_topic(sink, &topic).await {
+                        tracing::debug!("Pub/Sub subscribe failed, retried on reconnect: {}", e);
+                    }
+                }
+                receiver
+            }
+        };
+
+        if !state.running {
+            state.running = true;
+            tokio::spawn(run(self.inner.clone()));
+        }
+
+        Subscription {
+            topic,
+            receiver: Some(receiver),
+            hub: self.inner.clone(),
+        }
+    }
+}
+
+/// A client's view of a topic, unsubscribes from Redis when the last one of a
+/// topic is dropped
+pub struct Subscription {
+    topic: Topic,
+    receiver: Option<broadcast::Receiver<PubSubMessage>>,
+    hub: Arc<HubInner>,
+}
+
+impl Subscription {
+    /// Next message, `None` once the hub is gone.
+    ///
+    /// A subscriber too slow to keep up skips the messages it missed.
+    pub async fn recv(&mut self) -> Option<PubSubMessage> {
+        let receiver = self.receiver.as_mut()?;
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Some(message),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Pub/Sub subscriber lagging, {} message(s) skipped", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Release the receiver first so the count below no longer includes it
+        drop(self.receiver.take());
+
+        let hub = self.hub.clone();
+        let topic = self.topic.clone();
+        tokio::spawn(async move {
+            let mut state = hub.state.lock().await;
+            let unused = state
+                .topics
+                .get(&topic)
+                .is_some_and(|sender| sender.receiver_count() == 0);
+            if !unused {
+                return;
+            }
+
+            state.topics.remove(&topic);
+            if let Some(sink) = state.sink.as_mut() {
+                let result = match &topic {
+                    Topic::Channel(channel) => sink.unsubscribe(channel).await,
+                    Topic::Pattern(pattern) => sink.punsubscribe(pattern).await,
+                };
+                if let Err(e) = result {
+                    tracing::debug!("Pub/Sub unsubscribe failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+async fn subscribe_topic(sink: &mut PubSubSink, topic: &Topic) -> Result<(), RedisError> {
+    match topic {
+        Topic::Channel(channel) => sink.subscribe(channel).await,
+        Topic::Pattern(pattern) => sink.psubscribe(pattern).await,
+    }
+}
+
+/// Own the connection: connect, subscribe to every topic, then dispatch
+/// messages until the connection drops. Stops once no topic is left.
+async fn run(hub: Arc<HubInner>) {
+    let mut attempt = 0u32;
+    loop {
+        let stream = {
+            let mut state = hub.state.lock().await;
+            state.sink = None;
+            if state.topics.is_empty() {
+                state.running = false;
+                return;
+            }
+
+            match connect(&hub.pool, state.topics.keys()).await {
+                Ok((sink, stream)) => {
+                    if attempt > 0 {
+                        hub.pool
+                            .log_pubsub_info("Pub/Sub connection restored".to_string());
+                    }
+                    attempt = 0;
+                    state.sink = Some(sink);
+                    Some(stream)
+                }
+                Err(e) => {
+                    if attempt == 0 {
+                        hub.pool
+                            .log_pubsub_warn(format!("Pub/Sub connection failed: {}", e));
+                    }
+                    attempt += 1;
+                    None
+                }
+            }
+        };
+
+        match stream {
+            Some(mut stream) => {
+                while let Some(msg) = stream.next().await {
+                    dispatch(&hub, msg).await;
+                }
+                hub.pool
+                    .log_pubsub_warn("Pub/Sub connection lost, reconnecting".to_string());
+            }
+            None => sleep(RECONNECT_DELAY).await,
+        }
+    }
+}
+
+async fn connect(
+    pool: &RedisPool,
+    topics: impl Iterator<Item = &Topic>,
+) -> Result<(PubSubSink, PubSubStream), RedisError> {
+    let (mut sink, stream) = pool.pubsub_client().get_async_pubsub().await?.split();
+    for topic in topics {
+        subscribe_topic(&mut sink, topic).await?;
+    }
+    Ok((sink, stream))
+}
+
+async fn dispatch(hub: &HubInner, msg: Msg) {
+    let channel = msg.get_channel_name().to_string();
+    let pattern = if msg.from_pattern() {
+        msg.get_pattern::<String>().ok()
+    } else {
+        None
+    };
+    let topic = match &pattern {
+        Some(pattern) => Topic::Pattern(pattern.clone()),
+        None => Topic::Channel(channel.clone()),
+    };
+
+    let state = hub.state.lock().await;
+    if let Some(sender) = state.topics.get(&topic) {
+        // No receiver left means the topic is being unsubscribed
+        let _ = sender.send(PubSubMessage {
+            pattern,
+            channel,
+            payload: msg.get_payload_bytes().to_vec(),
+        });
+    }
+}
@@ -0,0 +1,69 @@
+use super::backend::RedisClient;
+use super::pool::ConnectionPool;
+use super::{build_client, node_connection_info};
+use crate::config::{Config, ReplicaStrategy};
+use redis::RedisError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Connection pools to the read replicas, one per endpoint
+#[derive(Clone)]
+pub struct ReplicaSet {
+    pools: Arc<Vec<ConnectionPool>>,
+    strategy: ReplicaStrategy,
+    next: Arc<AtomicUsize>,
+}
+
+impl ReplicaSet {
+    /// Build a pool per replica, sharing credentials, database and TLS
+    /// settings with the primary.
+    ///
+    /// Connections are opened lazily so an unreachable replica does not
+    /// prevent startup.
+    pub fn new(config: &Config, endpoints: &[String]) -> Result<Self, RedisError> {
+        let primary = config.redis_connection_info()?;
+
+        let pools = endpoints
+            .iter()
+            .map(|endpoint| {
+                let info = node_connection_info(&primary, endpoint)?;
+                let client = build_client(info, &config.redis_tls)?;
+                Ok(ConnectionPool::new(
+                    RedisClient::Single(client),
+                    config.max_connections,
+                ))
+            })
+            .collect::<Result<Vec<_>, RedisError>>()?;
+
+        Ok(Self {
+            pools: Arc::new(pools),
+            strategy: config.redis_replica_strategy,
+            next: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Pick the replica serving the next read
+    pub fn pick(&self) -> &ConnectionPool {
+        match self.strategy {
+            ReplicaStrategy::RoundRobin => {
+                let index = self.next.fetch_add(1, Ordering::Relaxed) % self.pools.len();
+                &self.pools[index]
+            }
+            ReplicaStrategy::LeastLoaded => self
+                .pools
+                .iter()
+                .min_by_key(|pool| {
+                    let stats = pool.stats();
+                    stats.in_use + stats.waiters
+                })
+                .expect("replica set is never empty"),
+        }
+    }
+
+    /// Close the connections to every replica
+    pub fn reconnect(&self) {
+        for pool in self.pools.iter() {
+            pool.reconnect();
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use redis::{Arg, Cmd, Pipeline, Script};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Scripts kept at most, so clients generating scripts on the fly cannot
+/// grow the registry without bound
+const MAX_SCRIPTS: usize = 1024;
+
+/// Lua scripts seen through EVAL or SCRIPT LOAD, by SHA1.
+///
+/// Redis forgets its script cache on restart or failover, the registry lets
+/// stashless load the scripts again and answer EVALSHA without a NOSCRIPT
+/// round trip to the client.
+#[derive(Clone, Default)]
+pub struct ScriptRegistry {
+    scripts: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl ScriptRegistry {
+    /// Remember the source of EVAL, EVAL_RO and SCRIPT LOAD commands
+    pub fn observe(&self, cmd: &Cmd) {
+        let args = simple_args(cmd);
+        let source = match args.as_slice() {
+            [name, source, ..]
+                if name.eq_ignore_ascii_case(b"EVAL") || name.eq_ignore_ascii_case(b"EVAL_RO") =>
+            {
+                source
+            }
+            [name, sub, source]
+                if name.eq_ignore_ascii_case(b"SCRIPT") && sub.eq_ignore_ascii_case(b"LOAD") =>
+            {
+                source
+            }
+            _ => return,
+        };
+        let Ok(source) = std::str::from_utf8(source) else {
+            return;
+        };
+
+        let sha = Script::new(source).get_hash().to_string();
+        let mut scripts = self.scripts.write().unwrap();
+        if scripts.len() < MAX_SCRIPTS || scripts.contains_key(&sha) {
+            scripts.insert(sha, source.to_string());
+        }
+    }
+
+    /// EVAL equivalent of an EVALSHA (or EVALSHA_RO) of a known script
+    pub fn eval_fallback(&self, cmd: &Cmd) -> Option<Cmd> {
+        let args = simple_args(cmd);
+        let (name, rest) = args.split_first()?;
+        let eval = if name.eq_ignore_ascii_case(b"EVALSHA") {
+            "EVAL"
+        } else if name.eq_ignore_ascii_case(b"EVALSHA_RO") {
+            "EVAL_RO"
+        } else {
+            return None;
+        };
+        let (sha, rest) = rest.split_first()?;
+
+        let sha = std::str::from_utf8(sha).ok()?.to_lowercase();
+        let source = self.scripts.read().unwrap().get(&sha)?.clone();
+
+        let mut fallback = redis::cmd(eval);
+        fallback.arg(source);
+        for arg in rest {
+            fallback.arg(*arg);
+        }
+        Some(fallback)
+    }
+
+    /// SCRIPT LOAD of every known script, `None` when there is none
+    pub fn load_pipeline(&self) -> Option<Pipeline> {
+        let scripts = self.scripts.read().unwrap();
+        if scripts.is_empty() {
+            return None;
+        }
+
+        let mut pipeline = Pipeline::with_capacity(scripts.len());
+        for source in scripts.values() {
+            pipeline.cmd("SCRIPT").arg("LOAD").arg(source).ignore();
+        }
+        Some(pipeline)
+    }
+
+    /// SHA1 of the known scripts, sorted
+    pub fn shas(&self) -> Vec<String> {
+        let mut shas: Vec<String> = self.scripts.read().unwrap().keys().cloned().collect();
+        shas.sort();
+        shas
+    }
+}
+
+fn simple_args(cmd: &Cmd) -> Vec<&[u8]> {
+    cmd.args_iter()
+        .filter_map(|arg| match arg {
+            Arg::Simple(arg) => Some(arg),
+            Arg::Cursor => None,
+        })
+        .collect()
+}
@@ -0,0 +1,121 @@
+use super::{build_client, RedisPool};
+use crate::config::{Config, SentinelConfig};
+use futures_util::StreamExt;
+use redis::sentinel::{Sentinel, SentinelNodeConnectionInfo};
+use redis::{Client, ConnectionInfo, RedisError, TlsMode};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Sentinel channel announcing a new master: "<name> <old-ip> <old-port> <new-ip> <new-port>"
+const SWITCH_MASTER_CHANNEL: &str = "+switch-master";
+
+/// Delay before trying the sentinels again when none of them answers
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Ask the sentinels for the address of the current master
+pub async fn discover_master(
+    config: &Config,
+    sentinel: &SentinelConfig,
+) -> Result<ConnectionInfo, RedisError> {
+    let mut sentinels = Sentinel::build(sentinel.sentinel_urls())?;
+    let node_info = node_connection_info(config)?;
+    let client = sentinels
+        .async_master_for(&sentinel.master_name, Some(&node_info))
+        .await?;
+    Ok(client.get_connection_info().clone())
+}
+
+/// Follow `+switch-master` events and move the pool to the new master
+pub fn spawn_master_watcher(
+    pool: RedisPool,
+    config: Config,
+    sentinel: SentinelConfig,
+    mut master: ConnectionInfo,
+) {
+    tokio::spawn(async move {
+        loop {
+            let mut pubsub = match subscribe_to_any(&sentinel).await {
+                Some(pubsub) => pubsub,
+                None => {
+                    sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            // A failover may have happened while we were not subscribed
+            refresh_master(&pool, &config, &sentinel, &mut master).await;
+
+            let mut messages = pubsub.on_message();
+            while let Some(message) = messages.next().await {
+                let payload: String = match message.get_payload() {
+                    Ok(payload) => payload,
+                    Err(_) => continue,
+                };
+                if payload.split_whitespace().next() == Some(sentinel.master_name.as_str()) {
+                    refresh_master(&pool, &config, &sentinel, &mut master).await;
+                }
+            }
+
+            tracing::warn!("Lost connection to Redis Sentinel, reconnecting");
+        }
+    });
+}
+
+async fn subscribe_to_any(sentinel: &SentinelConfig) -> Option<redis::aio::PubSub> {
+    for url in sentinel.sentinel_urls() {
+        let subscribed = async {
+            let client = Client::open(url.as_str())?;
+            let mut pubsub = client.get_async_pubsub().await?;
+            pubsub.subscribe(SWITCH_MASTER_CHANNEL).await?;
+            Ok::<_, RedisError>(pubsub)
+        };
+
+        match subscribed.await {
+            Ok(pubsub) => return Some(pubsub),
+            Err(e) => tracing::debug!("Sentinel {} unavailable: {}", url, e),
+        }
+    }
+    None
+}
+
+/// Re-discover the master and switch the pool over if it moved
+async fn refresh_master(
+    pool: &RedisPool,
+    config: &Config,
+    sentinel: &SentinelConfig,
+    master: &mut ConnectionInfo,
+) {
+    let discovered = match discover_master(config, sentinel).await {
+        Ok(discovered) => discovered,
+        Err(e) => {
+            tracing::warn!("Failed to discover Redis master from Sentinel: {}", e);
+            return;
+        }
+    };
+
+    if discovered.addr == master.addr {
+        return;
+    }
+
+    match build_client(discovered.clone(), &config.redis_tls) {
+        Ok(client) => {
+            pool.switch_master(client, &discovered, &sentinel.master_name);
+            *master = discovered;
+        }
+        Err(e) => tracing::error!("Failed to create client for new Redis master: {}", e),
+    }
+}
+
+/// Credentials, database and TLS mode used for the discovered nodes
+fn node_connection_info(config: &Config) -> Result<SentinelNodeConnectionInfo, RedisError> {
+    let tls_mode = match (config.redis_tls.enabled, config.redis_tls.insecure) {
+        (true, true) => Some(TlsMode::Insecure),
+        (true, false) => Some(TlsMode::Secure),
+        (false, _) => None,
+    };
+
+    Ok(SentinelNodeConnectionInfo {
+        tls_mode,
+        redis_connection_info: Some(config.redis_connection_info()?.redis),
+    })
+}
@@ -25,10 +25,15 @@ pub async fn handle_healthcheck() -> Result<(), String> {
     let token = std::env::var("SLASHLESS_TOKEN")
         .map_err(|_| "SLASHLESS_TOKEN is required for healthcheck".to_string())?;
 
-    let url = format!("http://{}:{}/health", host, port);
+    // The listener serves HTTPS when a certificate is configured; it is usually
+    // issued for a public name, so skip verification for this local probe
+    let tls = std::env::var("SLASHLESS_TLS_CERT").is_ok_and(|cert| !cert.is_empty());
+    let scheme = if tls { "https" } else { "http" };
+    let url = format!("{}://{}:{}/health", scheme, host, port);
 
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(5))
+        .danger_accept_invalid_certs(tls)
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
@@ -1,4 +1,14 @@
+use crate::utils::jwt::JwtVerifier;
+use crate::utils::policy::{split_rules, CommandPolicy};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use redis::{ConnectionAddr, ConnectionInfo, IntoConnectionInfo, RedisConnectionInfo, RedisError};
+use serde::{Deserialize, Serialize};
 use std::env;
+use std::fmt;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone)]
 pub enum ConsoleMode {
@@ -16,13 +26,237 @@ impl ConsoleMode {
     }
 }
 
+/// How read-only commands are spread over the replicas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplicaStrategy {
+    #[default]
+    RoundRobin,
+    /// Replica with the fewest connections in use or waited for
+    LeastLoaded,
+}
+
+impl ReplicaStrategy {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "round-robin" => Some(ReplicaStrategy::RoundRobin),
+            "least-loaded" => Some(ReplicaStrategy::LeastLoaded),
+            _ => None,
+        }
+    }
+}
+
+/// TLS settings for the upstream Redis connection
+#[derive(Debug, Clone, Default)]
+pub struct RedisTlsConfig {
+    /// Connect over TLS even when SLASHLESS_REDIS_URL is not a rediss:// URL
+    pub enabled: bool,
+    /// PEM bundle used instead of the system trust store
+    pub ca_cert: Option<String>,
+    /// PEM client certificate for mutual TLS
+    pub client_cert: Option<String>,
+    /// PEM client private key for mutual TLS
+    pub client_key: Option<String>,
+    /// Skip server certificate verification (development only)
+    pub insecure: bool,
+}
+
+impl RedisTlsConfig {
+    /// Whether custom certificates have to be loaded into the client
+    pub fn has_certificates(&self) -> bool {
+        self.ca_cert.is_some() || self.client_cert.is_some()
+    }
+}
+
+/// Redis Sentinel settings, the master address is discovered at runtime
+#[derive(Debug, Clone)]
+pub struct SentinelConfig {
+    /// Sentinel addresses, as host:port or redis:// URLs
+    pub sentinels: Vec<String>,
+    /// Name of the monitored master
+    pub master_name: String,
+    /// Password of the sentinels themselves (sentinel `requirepass`)
+    pub password: Option<String>,
+}
+
+impl SentinelConfig {
+    /// Connection URLs for the sentinels, with the password percent-encoded
+    pub fn sentinel_urls(&self) -> Vec<String> {
+        let credentials = self
+            .password
+            .as_ref()
+            .map(|password| format!(":{}@", utf8_percent_encode(password, NON_ALPHANUMERIC)))
+            .unwrap_or_default();
+
+        self.sentinels
+            .iter()
+            .map(|sentinel| {
+                if sentinel.contains("://") {
+                    sentinel.clone()
+                } else {
+                    format!("redis://{}{}", credentials, sentinel)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Request rate of a token bucket, written `100/s`, `600/m` or `10000/h`.
+///
+/// The bucket holds `requests` at most, so a full period worth of requests
+/// can come as a burst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn from_str(s: &str) -> Option<Self> {
+        let (requests, unit) = s.trim().split_once('/')?;
+        let period = match unit.trim().to_lowercase().as_str() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            _ => return None,
+        };
+        match requests.trim().parse::<u32>() {
+            Ok(requests) if requests > 0 => Some(Self { requests, period }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.period.as_secs() {
+            1 => "s",
+            60 => "m",
+            _ => "h",
+        };
+        write!(f, "{}/{}", self.requests, unit)
+    }
+}
+
+impl TryFrom<String> for RateLimit {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::from_str(&s).ok_or_else(|| format!("invalid rate limit {:?}, expected e.g. 100/s", s))
+    }
+}
+
+/// Rate limits applied to every request, unlimited when not set
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RateLimitConfig {
+    /// Whole server
+    pub global: Option<RateLimit>,
+    /// Each client IP address
+    pub per_ip: Option<RateLimit>,
+    /// Each token, unless it sets its own `rate_limit`
+    pub per_token: Option<RateLimit>,
+}
+
+/// What a token is allowed to run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// Read-only commands, like the Upstash read-only token
+    ReadOnly,
+    #[default]
+    ReadWrite,
+}
+
+/// Named API token, from SLASHLESS_TOKENS or SLASHLESS_TOKENS_FILE
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiToken {
+    /// Name shown in the logs instead of the secret
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub scope: TokenScope,
+    /// Unix timestamp (seconds) after which the token is refused
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Overrides SLASHLESS_RATE_LIMIT_TOKEN for this token
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    /// Prepended to every key, each prefix is a separate keyspace
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Commands and `@categories` allowed, SLASHLESS_ALLOW_COMMANDS if unset
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+    /// Commands and `@categories` denied, SLASHLESS_DENY_COMMANDS if unset
+    #[serde(default)]
+    pub deny: Option<Vec<String>>,
+    /// Resolved from the scope and the lists once the configuration is loaded
+    #[serde(skip)]
+    pub policy: CommandPolicy,
+    /// Set from the admin API, shared by every copy of the configuration
+    #[serde(skip)]
+    revoked: Arc<AtomicBool>,
+}
+
+impl ApiToken {
+    pub fn is_read_only(&self) -> bool {
+        self.scope == TokenScope::ReadOnly
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked.load(Ordering::Relaxed)
+    }
+
+    /// Refuse the token until the server restarts
+    pub fn revoke(&self) {
+        self.revoked.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .is_ok_and(|now| now.as_secs() >= expires_at)
+        })
+    }
+}
+
+/// Admin API, enabled by SLASHLESS_ADMIN_TOKEN
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    pub token: String,
+    /// Own listener port, the API is served under `/admin` when unset
+    pub port: Option<u16>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
+    pub redis_url: Option<String>,
     pub redis_host: String,
     pub redis_port: u16,
+    pub redis_username: Option<String>,
+    pub redis_password: Option<String>,
+    pub redis_tls: RedisTlsConfig,
+    pub redis_sentinel: Option<SentinelConfig>,
+    /// Seed nodes of a Redis Cluster (host:port or redis:// URLs)
+    pub redis_cluster_nodes: Option<Vec<String>>,
+    /// Read replicas (host:port or redis:// URLs) serving read-only commands
+    pub redis_replicas: Option<Vec<String>>,
+    pub redis_replica_strategy: ReplicaStrategy,
     pub host: String,
     pub port: u16,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
     pub token: String,
+    /// Every accepted token, SLASHLESS_TOKEN included as `default`
+    pub tokens: Vec<Arc<ApiToken>>,
+    /// Policy of requests made without authentication, refined by JWT claims
+    pub command_policy: Arc<CommandPolicy>,
+    /// JWT bearer tokens, accepted next to the static tokens
+    pub jwt: Option<JwtVerifier>,
+    pub rate_limit: RateLimitConfig,
+    pub admin: Option<AdminConfig>,
     pub max_connections: usize,
     pub max_retry: i32,
     pub console_mode: ConsoleMode,
@@ -30,6 +264,11 @@ pub struct Config {
 
 impl Config {
     pub fn from_env() -> Result<Self, String> {
+        // Full connection URL, takes precedence over host and port
+        let redis_url = env::var("SLASHLESS_REDIS_URL")
+            .ok()
+            .filter(|url| !url.is_empty());
+
         let redis_host =
             env::var("SLASHLESS_REDIS_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
 
@@ -38,6 +277,131 @@ impl Config {
             .parse::<u16>()
             .map_err(|_| "SLASHLESS_REDIS_PORT must be a valid port number")?;
 
+        // ACL username (Redis 6+), only sent when a password is configured
+        let redis_username = env::var("SLASHLESS_REDIS_USERNAME")
+            .ok()
+            .filter(|username| !username.is_empty());
+
+        // Password can come from the environment or from a mounted secret file
+        let redis_password = match (
+            env::var("SLASHLESS_REDIS_PASSWORD").ok(),
+            env::var("SLASHLESS_REDIS_PASSWORD_FILE").ok(),
+        ) {
+            (Some(_), Some(_)) => return Err(
+                "Set either SLASHLESS_REDIS_PASSWORD or SLASHLESS_REDIS_PASSWORD_FILE, not both"
+                    .to_string(),
+            ),
+            (Some(password), None) => Some(password),
+            (None, Some(path)) => Some(
+                fs::read_to_string(&path)
+                    .map_err(|e| {
+                        format!(
+                            "SLASHLESS_REDIS_PASSWORD_FILE could not be read ({}): {}",
+                            path, e
+                        )
+                    })?
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string(),
+            ),
+            (None, None) => None,
+        }
+        .filter(|password| !password.is_empty());
+
+        if redis_username.is_some() && redis_password.is_none() {
+            return Err(
+                "SLASHLESS_REDIS_USERNAME requires SLASHLESS_REDIS_PASSWORD or SLASHLESS_REDIS_PASSWORD_FILE"
+                    .to_string(),
+            );
+        }
+
+        if let Some(url) = &redis_url {
+            url.as_str().into_connection_info().map_err(|e| {
+                format!(
+                    "SLASHLESS_REDIS_URL must be a valid redis://, rediss:// or unix:// URL: {}",
+                    e
+                )
+            })?;
+        }
+
+        let redis_tls = RedisTlsConfig {
+            enabled: env_flag("SLASHLESS_REDIS_TLS")?,
+            ca_cert: env_path("SLASHLESS_REDIS_TLS_CA_CERT")?,
+            client_cert: env_path("SLASHLESS_REDIS_TLS_CERT")?,
+            client_key: env_path("SLASHLESS_REDIS_TLS_KEY")?,
+            insecure: env_flag("SLASHLESS_REDIS_TLS_INSECURE")?,
+        };
+
+        if redis_tls.client_cert.is_some() != redis_tls.client_key.is_some() {
+            return Err(
+                "SLASHLESS_REDIS_TLS_CERT and SLASHLESS_REDIS_TLS_KEY must be set together"
+                    .to_string(),
+            );
+        }
+
+        // Sentinel mode replaces the fixed Redis address
+        let redis_sentinel = match env::var("SLASHLESS_REDIS_SENTINELS") {
+            Ok(sentinels) if !sentinels.trim().is_empty() => {
+                if redis_url.is_some() {
+                    return Err(
+                        "SLASHLESS_REDIS_URL cannot be combined with SLASHLESS_REDIS_SENTINELS"
+                            .to_string(),
+                    );
+                }
+
+                Some(SentinelConfig {
+                    sentinels: parse_node_list("SLASHLESS_REDIS_SENTINELS", &sentinels)?,
+                    master_name: env::var("SLASHLESS_REDIS_SENTINEL_MASTER")
+                        .unwrap_or_else(|_| "mymaster".to_string()),
+                    password: env::var("SLASHLESS_REDIS_SENTINEL_PASSWORD")
+                        .ok()
+                        .filter(|password| !password.is_empty()),
+                })
+            }
+            _ => None,
+        };
+
+        // Cluster mode discovers the topology from the seed nodes
+        let redis_cluster_nodes = match env::var("SLASHLESS_REDIS_CLUSTER_NODES") {
+            Ok(nodes) if !nodes.trim().is_empty() => {
+                if redis_url.is_some() {
+                    return Err(
+                        "SLASHLESS_REDIS_URL cannot be combined with SLASHLESS_REDIS_CLUSTER_NODES"
+                            .to_string(),
+                    );
+                }
+                if redis_sentinel.is_some() {
+                    return Err(
+                        "SLASHLESS_REDIS_SENTINELS cannot be combined with SLASHLESS_REDIS_CLUSTER_NODES"
+                            .to_string(),
+                    );
+                }
+
+                Some(parse_node_list("SLASHLESS_REDIS_CLUSTER_NODES", &nodes)?)
+            }
+            _ => None,
+        };
+
+        // Read-only commands can be offloaded to replicas
+        let redis_replicas = match env::var("SLASHLESS_REDIS_REPLICAS") {
+            Ok(replicas) if !replicas.trim().is_empty() => {
+                if redis_cluster_nodes.is_some() {
+                    return Err(
+                        "SLASHLESS_REDIS_REPLICAS cannot be combined with SLASHLESS_REDIS_CLUSTER_NODES"
+                            .to_string(),
+                    );
+                }
+
+                Some(parse_node_list("SLASHLESS_REDIS_REPLICAS", &replicas)?)
+            }
+            _ => None,
+        };
+
+        let redis_replica_strategy = match env::var("SLASHLESS_REDIS_REPLICA_STRATEGY") {
+            Ok(strategy) => ReplicaStrategy::from_str(&strategy)
+                .ok_or("SLASHLESS_REDIS_REPLICA_STRATEGY must be round-robin or least-loaded")?,
+            Err(_) => ReplicaStrategy::default(),
+        };
+
         let host = env::var("SLASHLESS_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
 
         let port = env::var("SLASHLESS_PORT")
@@ -45,9 +409,158 @@ impl Config {
             .parse::<u16>()
             .map_err(|_| "SLASHLESS_PORT must be a valid port number")?;
 
+        // HTTPS on the listener, enabled when both certificate and key are provided
+        let tls_cert = env_path("SLASHLESS_TLS_CERT")?;
+        let tls_key = env_path("SLASHLESS_TLS_KEY")?;
+
+        if tls_cert.is_some() != tls_key.is_some() {
+            return Err(
+                "SLASHLESS_TLS_CERT and SLASHLESS_TLS_KEY must be set together".to_string(),
+            );
+        }
+
         // Token is now optional - if not provided, server runs without authentication
         let token = env::var("SLASHLESS_TOKEN").unwrap_or_default();
 
+        // Named tokens, as a JSON array inline or in a file
+        let mut tokens = match (
+            env::var("SLASHLESS_TOKENS").ok(),
+            env::var("SLASHLESS_TOKENS_FILE").ok(),
+        ) {
+            (Some(_), Some(_)) => {
+                return Err(
+                    "Set either SLASHLESS_TOKENS or SLASHLESS_TOKENS_FILE, not both".to_string(),
+                )
+            }
+            (Some(tokens), None) => parse_tokens(&tokens)
+                .map_err(|e| format!("SLASHLESS_TOKENS is not a valid token list: {}", e))?,
+            (None, Some(path)) => {
+                let tokens = fs::read_to_string(&path).map_err(|e| {
+                    format!("SLASHLESS_TOKENS_FILE could not be read ({}): {}", path, e)
+                })?;
+                parse_tokens(&tokens).map_err(|e| {
+                    format!("SLASHLESS_TOKENS_FILE is not a valid token list: {}", e)
+                })?
+            }
+            (None, None) => Vec::new(),
+        };
+
+        if !token.is_empty() {
+            tokens.insert(
+                0,
+                ApiToken {
+                    name: "default".to_string(),
+                    token: token.clone(),
+                    scope: TokenScope::ReadWrite,
+                    expires_at: None,
+                    rate_limit: None,
+                    prefix: None,
+                    allow: None,
+                    deny: None,
+                    policy: CommandPolicy::default(),
+                    revoked: Arc::default(),
+                },
+            );
+        }
+
+        for (i, api_token) in tokens.iter().enumerate() {
+            if api_token.name.is_empty() || api_token.token.is_empty() {
+                return Err("Tokens must have a non-empty name and token".to_string());
+            }
+            if let Some(other) = tokens[..i]
+                .iter()
+                .find(|other| other.name == api_token.name || other.token == api_token.token)
+            {
+                return Err(format!(
+                    "Tokens {} and {} must have different names and secrets",
+                    other.name, api_token.name
+                ));
+            }
+        }
+
+        // Commands allowed by default, dangerous ones are denied unless configured
+        let allow_commands = split_rules(&env::var("SLASHLESS_ALLOW_COMMANDS").unwrap_or_default());
+        let deny_commands = split_rules(
+            &env::var("SLASHLESS_DENY_COMMANDS").unwrap_or_else(|_| "@dangerous".to_string()),
+        );
+        let command_policy =
+            CommandPolicy::new(&allow_commands, &deny_commands, false).map_err(|e| {
+                format!(
+                    "Invalid SLASHLESS_ALLOW_COMMANDS or SLASHLESS_DENY_COMMANDS: {}",
+                    e
+                )
+            })?;
+
+        for api_token in &mut tokens {
+            if api_token.prefix.as_deref() == Some("") {
+                return Err(format!("Token {} has an empty prefix", api_token.name));
+            }
+
+            let policy = CommandPolicy::new(
+                api_token.allow.as_deref().unwrap_or(&allow_commands),
+                api_token.deny.as_deref().unwrap_or(&deny_commands),
+                api_token.is_read_only(),
+            )
+            .map_err(|e| format!("Invalid command lists of token {}: {}", api_token.name, e))?;
+            api_token.policy = match api_token.prefix {
+                Some(_) => policy.with_key_prefix(),
+                None => policy,
+            };
+        }
+
+        // Signed JWTs, minted by a backend for short-lived scoped access
+        let jwt = JwtVerifier::load(
+            env::var("SLASHLESS_JWT_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty())
+                .as_deref(),
+            env_path("SLASHLESS_JWT_JWKS_FILE")?.as_deref(),
+            env::var("SLASHLESS_JWT_ISSUER")
+                .ok()
+                .filter(|issuer| !issuer.is_empty()),
+            env::var("SLASHLESS_JWT_AUDIENCE")
+                .ok()
+                .filter(|audience| !audience.is_empty()),
+        )?;
+
+        let rate_limit = RateLimitConfig {
+            global: env_rate_limit("SLASHLESS_RATE_LIMIT_GLOBAL")?,
+            per_ip: env_rate_limit("SLASHLESS_RATE_LIMIT_IP")?,
+            per_token: env_rate_limit("SLASHLESS_RATE_LIMIT_TOKEN")?,
+        };
+
+        let admin = match env::var("SLASHLESS_ADMIN_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+        {
+            Some(admin_token) => {
+                if tokens
+                    .iter()
+                    .any(|api_token| api_token.token == admin_token)
+                {
+                    return Err("SLASHLESS_ADMIN_TOKEN must differ from the API tokens".to_string());
+                }
+                let admin_port = match env::var("SLASHLESS_ADMIN_PORT")
+                    .ok()
+                    .filter(|port| !port.is_empty())
+                {
+                    Some(admin_port) => Some(
+                        admin_port
+                            .parse::<u16>()
+                            .ok()
+                            .filter(|admin_port| *admin_port != port)
+                            .ok_or("SLASHLESS_ADMIN_PORT must be a valid port number, other than SLASHLESS_PORT")?,
+                    ),
+                    None => None,
+                };
+                Some(AdminConfig {
+                    token: admin_token,
+                    port: admin_port,
+                })
+            }
+            None => None,
+        };
+
         let max_connections = env::var("SLASHLESS_MAX_CONNECTION")
             .unwrap_or_else(|_| "3".to_string())
             .parse::<usize>()
@@ -65,38 +578,218 @@ impl Config {
             .parse::<i32>()
             .map_err(|_| "SLASHLESS_MAX_RETRY must be a valid integer")?;
 
-        Ok(Self {
+        let config = Self {
+            redis_url,
             redis_host,
             redis_port,
+            redis_username,
+            redis_password,
+            redis_tls,
+            redis_sentinel,
+            redis_cluster_nodes,
+            redis_replicas,
+            redis_replica_strategy,
             host,
             port,
+            tls_cert,
+            tls_key,
             token,
+            tokens: tokens.into_iter().map(Arc::new).collect(),
+            command_policy: Arc::new(command_policy),
+            jwt,
+            rate_limit,
+            admin,
             max_connections,
             max_retry,
             console_mode,
-        })
+        };
+
+        // Validate the Redis target (URL syntax, TLS options) before anything connects
+        config
+            .redis_connection_info()
+            .map_err(|e| format!("Invalid Redis configuration: {}", e))?;
+
+        Ok(config)
     }
 
-    pub fn redis_url(&self) -> String {
-        format!("redis://{}:{}", self.redis_host, self.redis_port)
+    /// Connection settings for Redis, from SLASHLESS_REDIS_URL when set.
+    ///
+    /// Credentials embedded in the URL win over SLASHLESS_REDIS_USERNAME and
+    /// SLASHLESS_REDIS_PASSWORD, which only fill in what the URL leaves out.
+    pub fn redis_connection_info(&self) -> Result<ConnectionInfo, RedisError> {
+        let mut info = match &self.redis_url {
+            Some(url) => url.as_str().into_connection_info()?,
+            None => ConnectionInfo {
+                addr: ConnectionAddr::Tcp(self.redis_host.clone(), self.redis_port),
+                redis: RedisConnectionInfo::default(),
+            },
+        };
+
+        info.addr = match info.addr {
+            ConnectionAddr::Tcp(host, port) if self.redis_tls.enabled => ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure: self.redis_tls.insecure,
+                tls_params: None,
+            },
+            ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure,
+                tls_params,
+            } => ConnectionAddr::TcpTls {
+                host,
+                port,
+                insecure: insecure || self.redis_tls.insecure,
+                tls_params,
+            },
+            addr => {
+                if self.redis_tls.has_certificates() || self.redis_tls.insecure {
+                    return Err(RedisError::from((
+                        redis::ErrorKind::InvalidClientConfig,
+                        "TLS options require a rediss:// URL or SLASHLESS_REDIS_TLS=true",
+                    )));
+                }
+                addr
+            }
+        };
+
+        if info.redis.password.is_none() {
+            info.redis.password = self.redis_password.clone();
+            if info.redis.username.is_none() {
+                info.redis.username = self.redis_username.clone();
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Redis address for display, with the password masked
+    pub fn redis_address(&self) -> String {
+        if let Some(sentinel) = &self.redis_sentinel {
+            return format!(
+                "sentinel://{}@{}",
+                sentinel.master_name,
+                sentinel.sentinels.join(",")
+            );
+        }
+        if let Some(nodes) = &self.redis_cluster_nodes {
+            return format!("cluster://{}", nodes.join(","));
+        }
+
+        let info = match self.redis_connection_info() {
+            Ok(info) => info,
+            Err(_) => return format!("{}:{}", self.redis_host, self.redis_port),
+        };
+
+        let credentials = match (&info.redis.username, &info.redis.password) {
+            (Some(username), Some(password)) => format!("{}:{}@", username, mask_secret(password)),
+            (None, Some(password)) => format!(":{}@", mask_secret(password)),
+            _ => String::new(),
+        };
+
+        let (mut address, db_separator) = match &info.addr {
+            ConnectionAddr::Tcp(host, port) => {
+                (format!("redis://{}{}:{}", credentials, host, port), "/")
+            }
+            ConnectionAddr::TcpTls { host, port, .. } => {
+                (format!("rediss://{}{}:{}", credentials, host, port), "/")
+            }
+            ConnectionAddr::Unix(path) => {
+                (format!("unix://{}{}", credentials, path.display()), "?db=")
+            }
+        };
+        if info.redis.db != 0 {
+            address.push_str(&format!("{}{}", db_separator, info.redis.db));
+        }
+        address
     }
 
     pub fn server_address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
 
+    /// Address of the admin listener, when it has its own port
+    pub fn admin_address(&self) -> Option<String> {
+        let port = self.admin.as_ref()?.port?;
+        Some(format!("{}:{}", self.host, port))
+    }
+
     #[allow(dead_code)]
     pub fn masked_token(&self) -> String {
         if self.token.is_empty() {
             "none (no security)".to_string()
-        } else if self.token.len() <= 8 {
-            format!("{}***", &self.token[..1.min(self.token.len())])
         } else {
-            format!("{}***", &self.token[..8])
+            mask_secret(&self.token)
         }
     }
 
     pub fn is_secure(&self) -> bool {
-        !self.token.is_empty()
+        !self.tokens.is_empty() || self.jwt.is_some()
+    }
+
+    pub fn is_tls(&self) -> bool {
+        self.tls_cert.is_some() && self.tls_key.is_some()
+    }
+}
+
+fn mask_secret(secret: &str) -> String {
+    let visible = if secret.len() <= 8 { 1 } else { 8 };
+    let end = secret
+        .char_indices()
+        .nth(visible)
+        .map(|(i, _)| i)
+        .unwrap_or(secret.len());
+    format!("{}***", &secret[..end])
+}
+
+/// Parse a JSON token list: `[{"name": ..., "token": ..., "scope": ...}]`
+fn parse_tokens(tokens: &str) -> Result<Vec<ApiToken>, serde_json::Error> {
+    serde_json::from_str(tokens)
+}
+
+/// Parse a comma-separated list of nodes, which must not be empty once the
+/// blank entries are dropped (`SLASHLESS_REDIS_SENTINELS=","`)
+fn parse_node_list(name: &str, nodes: &str) -> Result<Vec<String>, String> {
+    let nodes: Vec<String> = nodes
+        .split(',')
+        .map(|node| node.trim().to_string())
+        .filter(|node| !node.is_empty())
+        .collect();
+    if nodes.is_empty() {
+        return Err(format!("{} must list at least one host:port", name));
+    }
+    Ok(nodes)
+}
+
+/// Parse a boolean flag such as SLASHLESS_REDIS_TLS (unset means false)
+fn env_flag(name: &str) -> Result<bool, String> {
+    match env::var(name) {
+        Ok(value) => match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "" | "0" | "false" | "no" | "off" => Ok(false),
+            _ => Err(format!("{} must be true or false", name)),
+        },
+        Err(_) => Ok(false),
+    }
+}
+
+/// Parse an optional rate limit such as SLASHLESS_RATE_LIMIT_IP=100/s
+fn env_rate_limit(name: &str) -> Result<Option<RateLimit>, String> {
+    match env::var(name).ok().filter(|limit| !limit.trim().is_empty()) {
+        Some(limit) => RateLimit::from_str(&limit)
+            .map(Some)
+            .ok_or_else(|| format!("{} must be a rate such as 100/s, 600/m or 10000/h", name)),
+        None => Ok(None),
+    }
+}
+
+/// Read an optional file path, failing early if the file does not exist
+fn env_path(name: &str) -> Result<Option<String>, String> {
+    match env::var(name).ok().filter(|path| !path.is_empty()) {
+        Some(path) if !std::path::Path::new(&path).is_file() => {
+            Err(format!("{} points to a missing file: {}", name, path))
+        }
+        path => Ok(path),
     }
 }
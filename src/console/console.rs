@@ -1,3 +1,4 @@
+use crate::client::PoolStats;
 use crate::config::ConsoleMode;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
@@ -19,6 +20,7 @@ pub struct Console {
 }
 
 impl Console {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         server_address: String,
         redis_address: String,
@@ -27,6 +29,7 @@ impl Console {
         version: String,
         mode: ConsoleMode,
         is_secure: bool,
+        is_tls: bool,
     ) -> io::Result<(Self, Option<mpsc::UnboundedReceiver<()>>)> {
         match mode {
             ConsoleMode::Standard => {
@@ -42,6 +45,7 @@ impl Console {
                         version,
                         ConsoleMode::Boring,
                         is_secure,
+                        is_tls,
                     );
                 }
 
@@ -67,6 +71,7 @@ impl Console {
                     max_retry,
                     version.clone(),
                     is_secure,
+                    is_tls,
                 );
 
                 // Initialize terminal in a blocking task
@@ -121,6 +126,18 @@ impl Console {
                                     state.redis_status = status;
                                     should_render = true;
                                 }
+                                ConsoleCommand::UpdatePoolStats(stats) => {
+                                    state.pool_stats = stats;
+                                    should_render = true;
+                                }
+                                ConsoleCommand::UpdateRateLimited(count) => {
+                                    state.rate_limited = count;
+                                    should_render = true;
+                                }
+                                ConsoleCommand::UpdateRedisAddress(address) => {
+                                    state.redis_address = address;
+                                    should_render = true;
+                                }
                             }
                         }
 
@@ -160,6 +177,7 @@ impl Console {
                 };
                 tracing::info!("Max retry: {}", retry_display);
                 tracing::info!("Version: {}", version);
+                tracing::info!("TLS: {}", if is_tls { "enabled" } else { "disabled" });
 
                 // Show security warning if not secure
                 if !is_secure {
@@ -205,6 +223,61 @@ impl Console {
         Ok(())
     }
 
+    pub fn update_redis_address(&self, address: String) -> io::Result<()> {
+        match &self.mode {
+            ConsoleMode::Standard => {
+                if let Some(ref sender) = self.sender {
+                    sender
+                        .send(ConsoleCommand::UpdateRedisAddress(address))
+                        .map_err(|e| io::Error::other(format!("Failed to send command: {}", e)))?;
+                }
+            }
+            ConsoleMode::Boring => {
+                tracing::info!("Redis: {}", address);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_pool_stats(&self, stats: PoolStats) -> io::Result<()> {
+        match &self.mode {
+            ConsoleMode::Standard => {
+                if let Some(ref sender) = self.sender {
+                    sender
+                        .send(ConsoleCommand::UpdatePoolStats(stats))
+                        .map_err(|e| io::Error::other(format!("Failed to send command: {}", e)))?;
+                }
+            }
+            ConsoleMode::Boring => {
+                tracing::debug!(
+                    "Pool: {} in use, {} idle, {} waiting ({} open of {})",
+                    stats.in_use,
+                    stats.idle,
+                    stats.waiters,
+                    stats.open,
+                    stats.size
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_rate_limited(&self, count: u64) -> io::Result<()> {
+        match &self.mode {
+            ConsoleMode::Standard => {
+                if let Some(ref sender) = self.sender {
+                    sender
+                        .send(ConsoleCommand::UpdateRateLimited(count))
+                        .map_err(|e| io::Error::other(format!("Failed to send command: {}", e)))?;
+                }
+            }
+            ConsoleMode::Boring => {
+                tracing::debug!("Rate limited: {} requests", count);
+            }
+        }
+        Ok(())
+    }
+
     pub fn log_info(&self, message: String) -> io::Result<()> {
         match &self.mode {
             ConsoleMode::Standard => {
@@ -254,7 +327,6 @@ impl Console {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn mode(&self) -> &ConsoleMode {
         &self.mode
     }
@@ -20,8 +20,8 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
         ConsoleMode::Standard => Layout::vertical([
             Constraint::Length(banner_height),
             Constraint::Length(1),
-            Constraint::Length(3),
             Constraint::Length(4),
+            Constraint::Length(5),
         ])
         .split(area),
         ConsoleMode::Boring => {
@@ -30,7 +30,7 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
                 Constraint::Length(banner_height),
                 Constraint::Length(1),
                 Constraint::Length(3),
-                Constraint::Length(4),
+                Constraint::Length(5),
             ])
             .split(area)
         }
@@ -39,12 +39,13 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
     // Render banner
     render_banner(f, vertical[0], &state.version);
 
-    // Status section (now with 3 lines: Server, Redis, Security)
+    // Status section (4 lines: Server, Redis, Security, TLS)
     let status_area = vertical[2];
     let status_layout = Layout::vertical([
         Constraint::Length(1),
         Constraint::Length(1),
         Constraint::Length(1),
+        Constraint::Length(1),
     ])
     .split(status_area);
 
@@ -108,6 +109,31 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
     };
     f.render_widget(security_para, security_area);
 
+    // TLS status
+    let tls_label = "TLS Status        ";
+    let (tls_value, tls_style) = if state.is_tls {
+        (
+            "HTTPS",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        ("PLAINTEXT", Style::default().fg(Color::Yellow))
+    };
+    let tls_line = Line::from(vec![
+        Span::styled(tls_label, Style::default()),
+        Span::styled(tls_value, tls_style),
+    ]);
+    let tls_para = Paragraph::new(tls_line).alignment(Alignment::Left);
+    let tls_area = Rect {
+        x: status_layout[3].x + 2,
+        y: status_layout[3].y,
+        width: status_layout[3].width.saturating_sub(2),
+        height: status_layout[3].height,
+    };
+    f.render_widget(tls_para, tls_area);
+
     // Config section
     let config_area = vertical[3];
     let config_layout = Layout::vertical([
@@ -115,6 +141,7 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
         Constraint::Length(1),
         Constraint::Length(1),
         Constraint::Length(1),
+        Constraint::Length(1),
     ])
     .split(config_area);
 
@@ -159,7 +186,13 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
     let connections_config_line = Line::from(vec![
         Span::styled(connections_config_label, Style::default().fg(Color::Gray)),
         Span::styled(
-            state.connections.to_string(),
+            format!(
+                "{} ({} in use, {} idle, {} waiting)",
+                state.connections,
+                state.pool_stats.in_use,
+                state.pool_stats.idle,
+                state.pool_stats.waiters
+            ),
             Style::default().fg(Color::DarkGray),
         ),
     ]);
@@ -191,6 +224,24 @@ pub fn render_console(f: &mut Frame, state: &ConsoleState, mode: &ConsoleMode) {
         height: config_layout[3].height,
     };
     f.render_widget(max_retry_config, max_retry_config_area);
+
+    // Rate limited requests
+    let rate_limited_config_label = "Rate Limited      ";
+    let rate_limited_config_line = Line::from(vec![
+        Span::styled(rate_limited_config_label, Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("{} requests", state.rate_limited),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    let rate_limited_config = Paragraph::new(rate_limited_config_line).alignment(Alignment::Left);
+    let rate_limited_config_area = Rect {
+        x: config_layout[4].x + 2,
+        y: config_layout[4].y,
+        width: config_layout[4].width.saturating_sub(2),
+        height: config_layout[4].height,
+    };
+    f.render_widget(rate_limited_config, rate_limited_config_area);
 }
 
 pub fn render_banner(f: &mut Frame, area: Rect, version: &str) {
@@ -1,8 +1,13 @@
 use super::status::Status;
+use crate::client::PoolStats;
 
+#[allow(clippy::enum_variant_names)]
 pub enum ConsoleCommand {
     UpdateServerStatus(Status),
     UpdateRedisStatus(Status),
+    UpdatePoolStats(PoolStats),
+    UpdateRateLimited(u64),
+    UpdateRedisAddress(String),
 }
 
 pub struct ConsoleState {
@@ -11,9 +16,12 @@ pub struct ConsoleState {
     pub server_address: String,
     pub redis_address: String,
     pub connections: usize,
+    pub pool_stats: PoolStats,
+    pub rate_limited: u64,
     pub max_retry: i32,
     pub version: String,
     pub is_secure: bool,
+    pub is_tls: bool,
 }
 
 impl ConsoleState {
@@ -24,6 +32,7 @@ impl ConsoleState {
         max_retry: i32,
         version: String,
         is_secure: bool,
+        is_tls: bool,
     ) -> Self {
         Self {
             server_status: Status::Starting,
@@ -31,9 +40,15 @@ impl ConsoleState {
             server_address,
             redis_address,
             connections,
+            pool_stats: PoolStats {
+                size: connections,
+                ..PoolStats::default()
+            },
+            rate_limited: 0,
             max_retry,
             version,
             is_secure,
+            is_tls,
         }
     }
 }
@@ -3,6 +3,7 @@ use ratatui::style::{Color, Modifier, Style};
 pub enum Status {
     Connected,
     Ready,
+    Maintenance,
     Starting,
     Establishing,
     #[allow(dead_code)]
@@ -10,6 +11,7 @@ pub enum Status {
     Reconnecting,
     BindError,
     ConnectionError,
+    AuthError,
 }
 
 impl Status {
@@ -17,12 +19,14 @@ impl Status {
         match self {
             Status::Connected => "CONNECTED",
             Status::Ready => "READY",
+            Status::Maintenance => "MAINTENANCE",
             Status::Starting => "STARTING",
             Status::Establishing => "ESTABLISHING CONNECTION",
             Status::Disconnected => "DISCONNECTED",
             Status::Reconnecting => "RECONNECTING",
             Status::BindError => "BIND ERROR",
             Status::ConnectionError => "CONNECTION ERROR",
+            Status::AuthError => "AUTH ERROR",
         }
     }
 
@@ -30,12 +34,14 @@ impl Status {
         match self {
             Status::Connected => Color::Green,
             Status::Ready => Color::Green,
+            Status::Maintenance => Color::Yellow,
             Status::Starting => Color::Gray,
             Status::Establishing => Color::DarkGray,
             Status::Disconnected => Color::Yellow,
             Status::Reconnecting => Color::Gray,
             Status::BindError => Color::Red,
             Status::ConnectionError => Color::Red,
+            Status::AuthError => Color::Red,
         }
     }
 
@@ -45,9 +51,11 @@ impl Status {
                 self,
                 Status::Connected
                     | Status::Ready
+                    | Status::Maintenance
                     | Status::Disconnected
                     | Status::BindError
                     | Status::ConnectionError
+                    | Status::AuthError
             ) {
                 Modifier::BOLD
             } else {
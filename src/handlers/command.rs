@@ -1,15 +1,19 @@
-use crate::client::RedisPool;
-use crate::utils::encoding::encode_result;
+use crate::client::{RedisPool, Target};
+use crate::utils::body::BodyValue;
+use crate::utils::commands::changes_connection_state;
+use crate::utils::encoding::{build_command, bulk_string_to_body, command_args, text_to_body};
+use crate::utils::options::{RequestOptions, ResponseFormat};
+use crate::utils::resp::{format_server_error, resp_response};
 use crate::utils::AppError;
 use axum::extract::State;
-use axum::Json;
-use serde_json::{json, Value};
+use axum::response::Response;
+use base64::{engine::general_purpose, Engine as _};
 
 pub async fn handle_command_internal(
     State(pool): State<RedisPool>,
-    Json(body): Json<Value>,
-    encoding_enabled: bool,
-) -> Result<Json<Value>, AppError> {
+    body: BodyValue,
+    options: RequestOptions<'_>,
+) -> Result<Response, AppError> {
     // Extract command array from body
     // Upstash sends [["command", "arg1"]] format (array of arrays), but we also support ["command", "arg1"] format
     // Also support direct array: [["command", "arg1"]] or {"_json": [["command", "arg1"]]}
@@ -35,66 +39,162 @@ pub async fn handle_command_internal(
         json_array
     };
 
-    // Convert JSON array to Vec<String>
+    // Convert JSON array to raw arguments
     // Handle both strings and numbers (e.g., LRANGE takes numeric indices)
-    let cmd_args: Vec<String> = command_array
-        .iter()
-        .map(|v| match v {
-            Value::String(s) => s.clone(),
-            Value::Number(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::Null => "".to_string(),
-            _ => v.to_string(),
-        })
-        .collect();
-
-    if cmd_args.is_empty() {
-        return Err(AppError::MalformedRequest(
-            "Command array cannot be empty".to_string(),
-        ));
-    }
+    let cmd_args = command_args(command_array, options.base64_args)?;
+    options.check_allowed(&cmd_args)?;
+    reject_connection_state(&cmd_args)?;
+    let cmd_args = options.prefix_keys(cmd_args);
 
     // Build Redis command
-    let mut cmd = redis::cmd(&cmd_args[0]);
-    for arg in cmd_args.iter().skip(1) {
-        cmd.arg(arg);
+    let cmd = build_command(&cmd_args)
+        .ok_or_else(|| AppError::MalformedRequest("Command array cannot be empty".to_string()))?;
+
+    // Execute command, read-only ones may be served by a replica
+    let target = if options.resp3 {
+        Target::Resp3
+    } else {
+        pool.read_target([&cmd], options.read_primary)
+    };
+
+    // RESP re-encoding: the reply is encoded back to RESP, error replies included
+    if options.format == ResponseFormat::Resp {
+        let value = pool.execute_command_raw(cmd, target).await?;
+        let value = options.strip_keys(&cmd_args[0], value);
+        return Ok(resp_response(&[value], options.resp3));
     }
 
-    // Execute command
-    let result = pool.execute_command(cmd).await.map_err(AppError::Redis);
+    let result = pool
+        .execute_command(cmd, target)
+        .await
+        .map_err(AppError::Redis);
 
-    match result {
+    let response = match result {
         Ok(redis_value) => {
-            let json_value = redis_value_to_json(redis_value);
-            let response = json!({ "result": json_value });
-            let encoded = encode_result(&response, encoding_enabled);
-            Ok(Json(encoded))
+            let redis_value = options.strip_keys(&cmd_args[0], redis_value);
+            let value = redis_value_to_body(redis_value, options);
+            BodyValue::object([("result", value)])
         }
         Err(e) => {
             let error_msg = e.to_string();
-            let response = json!({ "error": error_msg });
-            Ok(Json(response))
+            BodyValue::object([("error", error_msg.into())])
+        }
+    };
+    Ok(options.render(response))
+}
+
+/// Prefix of the map keys given as the base64 of their bytes
+const BASE64_KEY_PREFIX: &str = "base64:";
+
+/// Object key of a RESP3 map entry.
+///
+/// Keys that are not valid UTF-8, or that would make the map look like a
+/// tagged value (`{"base64": ...}`, `{"bignum": ...}`, `{"verbatim": ...}`),
+/// are written as `base64:` followed by the base64 of the key. Keys starting
+/// with `base64:` are escaped the same way, so the form is never ambiguous.
+fn map_key(key: BodyValue) -> String {
+    let key = match key {
+        BodyValue::String(key) => key.into_bytes(),
+        BodyValue::Bytes(key) => key,
+        key => return key.to_string(),
+    };
+    let escaped = |key: &[u8]| {
+        format!(
+            "{}{}",
+            BASE64_KEY_PREFIX,
+            general_purpose::STANDARD.encode(key)
+        )
+    };
+    match String::from_utf8(key) {
+        Ok(key)
+            if matches!(key.as_str(), "base64" | "bignum" | "verbatim")
+                || key.starts_with(BASE64_KEY_PREFIX) =>
+        {
+            escaped(key.as_bytes())
         }
+        Ok(key) => key,
+        Err(e) => escaped(e.as_bytes()),
+    }
+}
+
+/// Refuse commands that would change the state of a pooled connection, the
+/// next request using it could belong to another token
+pub(crate) fn reject_connection_state(args: &[Vec<u8>]) -> Result<(), AppError> {
+    if changes_connection_state(args) {
+        return Err(AppError::MalformedRequest(format!(
+            "ERR '{}' changes the state of the Redis connection and is not supported, use /multi-exec for transactions and /subscribe for Pub/Sub",
+            String::from_utf8_lossy(&args[0]).to_lowercase()
+        )));
     }
+    Ok(())
 }
 
-pub(crate) fn redis_value_to_json(value: redis::Value) -> Value {
+/// Response form of a Redis reply, see `bulk_string_to_body` for how binary
+/// values are represented.
+///
+/// RESP2 shapes are kept unless the request asked for RESP3: maps are then
+/// objects (see `map_key`), doubles numbers and booleans booleans, while big
+/// numbers and verbatim strings become `{"bignum": ...}` and
+/// `{"verbatim": ..., "format": ...}`.
+pub(crate) fn redis_value_to_body(value: redis::Value, options: RequestOptions) -> BodyValue {
+    let to_body = |value| redis_value_to_body(value, options);
     match value {
-        redis::Value::Nil => Value::Null,
-        redis::Value::Int(i) => Value::Number(i.into()),
-        redis::Value::Data(bytes) => {
-            // Try to decode as UTF-8 string, otherwise return as base64
-            String::from_utf8(bytes.clone())
-                .map(Value::String)
-                .unwrap_or_else(|_| {
-                    use base64::{engine::general_purpose, Engine as _};
-                    Value::String(general_purpose::STANDARD.encode(&bytes))
-                })
+        redis::Value::Nil => BodyValue::Null,
+        redis::Value::Int(i) => i.into(),
+        redis::Value::BulkString(bytes) => bulk_string_to_body(bytes, options),
+        redis::Value::Array(values) | redis::Value::Set(values) => {
+            BodyValue::Array(values.into_iter().map(to_body).collect())
+        }
+        redis::Value::Map(pairs) if options.resp3 => BodyValue::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (map_key(to_body(key)), to_body(value)))
+                .collect(),
+        ),
+        // Maps are flattened to [key, value, ...] like their RESP2 form
+        redis::Value::Map(pairs) => BodyValue::Array(
+            pairs
+                .into_iter()
+                .flat_map(|(key, value)| [to_body(key), to_body(value)])
+                .collect(),
+        ),
+        redis::Value::Attribute { data, .. } => to_body(*data),
+        redis::Value::Push { data, .. } => {
+            BodyValue::Array(data.into_iter().map(to_body).collect())
+        }
+        redis::Value::SimpleString(s) => text_to_body(s, options),
+        redis::Value::VerbatimString { format, text } if options.resp3 => BodyValue::object([
+            ("verbatim", text_to_body(text, options)),
+            ("format", format.to_string().into()),
+        ]),
+        redis::Value::VerbatimString { text, .. } => text_to_body(text, options),
+        redis::Value::Okay => text_to_body("OK".to_string(), options),
+        // JSON has no infinity nor NaN, those are sent as Redis writes them
+        redis::Value::Double(d) if options.resp3 && !d.is_finite() => match d {
+            d if d.is_nan() => "nan",
+            d if d > 0.0 => "inf",
+            _ => "-inf",
         }
-        redis::Value::Bulk(values) => {
-            Value::Array(values.into_iter().map(redis_value_to_json).collect())
+        .into(),
+        redis::Value::Double(d) => {
+            serde_json::Number::from_f64(d).map_or(BodyValue::Null, BodyValue::Number)
         }
-        redis::Value::Status(s) => Value::String(s),
-        redis::Value::Okay => Value::String("OK".to_string()),
+        redis::Value::Boolean(b) if options.resp3 => BodyValue::Bool(b),
+        redis::Value::Boolean(b) => i64::from(b).into(),
+        redis::Value::BigNumber(n) if options.resp3 => {
+            BodyValue::object([("bignum", n.to_string().into())])
+        }
+        redis::Value::BigNumber(n) => text_to_body(n.to_string(), options),
+        redis::Value::ServerError(e) => {
+            text_to_body(format_server_error(e.code(), e.details()), options)
+        }
+    }
+}
+
+/// Message of an error reply, as Redis sends it (`WRONGTYPE Operation ...`)
+pub(crate) fn server_error_message(value: &redis::Value) -> Option<String> {
+    match value {
+        redis::Value::ServerError(e) => Some(format_server_error(e.code(), e.details())),
+        _ => None,
     }
 }
@@ -1,3 +1,5 @@
 pub mod command;
+pub mod path;
 pub mod pipeline;
+pub mod subscribe;
 pub mod transaction;
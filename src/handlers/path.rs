@@ -0,0 +1,64 @@
+use crate::client::RedisPool;
+use crate::handlers::command::handle_command_internal;
+use crate::utils::body::BodyValue;
+use crate::utils::options::RequestOptions;
+use crate::utils::AppError;
+use axum::extract::State;
+use axum::response::Response;
+use percent_encoding::percent_decode_str;
+
+/// Build the command array of an Upstash path-style request.
+///
+/// `GET /set/foo/bar?EX=100` becomes `["set", "foo", "bar", "EX", "100"]`:
+/// path segments are URL-decoded one by one (so `%2F` stays inside its
+/// argument), query options follow as name/value pairs (a bare `?NX` adds only
+/// the name) and a non-empty request body is appended as the last argument.
+/// Arguments are binary-safe: `%FF` in a segment and raw body bytes are kept.
+pub fn path_command_args(
+    path: &str,
+    query: &[(String, String)],
+    body: &[u8],
+) -> Result<Vec<BodyValue>, AppError> {
+    let mut args = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| {
+            let arg: Vec<u8> = percent_decode_str(segment).collect();
+            String::from_utf8(arg)
+                .map(BodyValue::String)
+                .unwrap_or_else(|e| BodyValue::Bytes(e.into_bytes()))
+        })
+        .collect::<Vec<_>>();
+
+    // A trailing slash leaves an empty segment that is not an argument
+    if args.last() == Some(&BodyValue::String(String::new())) && args.len() > 1 {
+        args.pop();
+    }
+
+    for (name, value) in query {
+        args.push(BodyValue::String(name.clone()));
+        if !value.is_empty() {
+            args.push(BodyValue::String(value.clone()));
+        }
+    }
+
+    // The body is passed as is, so binary values can be stored
+    if !body.is_empty() {
+        args.push(BodyValue::Bytes(body.to_vec()));
+    }
+
+    Ok(args)
+}
+
+pub async fn handle_path_command_internal(
+    State(pool): State<RedisPool>,
+    args: Vec<BodyValue>,
+    options: RequestOptions<'_>,
+) -> Result<Response, AppError> {
+    // Same execution path as a JSON command, arguments are never base64
+    let options = RequestOptions {
+        base64_args: false,
+        ..options
+    };
+    handle_command_internal(State(pool), BodyValue::Array(args), options).await
+}
@@ -1,16 +1,18 @@
-use crate::client::RedisPool;
-use crate::handlers::command::redis_value_to_json;
-use crate::utils::encoding::encode_result;
+use crate::client::{RedisPool, Target};
+use crate::handlers::command::{redis_value_to_body, reject_connection_state};
+use crate::utils::body::BodyValue;
+use crate::utils::encoding::{build_command, command_args, command_name};
+use crate::utils::options::{RequestOptions, ResponseFormat};
+use crate::utils::resp::resp_response;
 use crate::utils::AppError;
 use axum::extract::State;
-use axum::Json;
-use serde_json::{json, Value};
+use axum::response::Response;
 
 pub async fn handle_pipeline_internal(
     State(pool): State<RedisPool>,
-    Json(body): Json<Value>,
-    encoding_enabled: bool,
-) -> Result<Json<Value>, AppError> {
+    body: BodyValue,
+    options: RequestOptions<'_>,
+) -> Result<Response, AppError> {
     // Extract array of command arrays from body
     // Upstash sends directly as array: [[...]], but we also support {"_json": [[...]]}
     let command_arrays = if body.is_array() {
@@ -39,9 +41,7 @@ pub async fn handle_pipeline_internal(
 
     // Handle empty pipeline
     if command_arrays.is_empty() {
-        let response = Value::Array(vec![]);
-        let encoded = encode_result(&response, encoding_enabled);
-        return Ok(Json(encoded));
+        return Ok(empty_response(options));
     }
 
     // Build pipeline
@@ -49,81 +49,89 @@ pub async fn handle_pipeline_internal(
     let mut has_commands = false;
 
     for cmd_array in command_arrays {
-        let cmd_args: Vec<String> = cmd_array
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|v| match v {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                Value::Null => "".to_string(),
-                _ => v.to_string(),
-            })
-            .collect();
+        let cmd_args = command_args(cmd_array.as_array().unwrap(), options.base64_args)?;
+        options.check_allowed(&cmd_args)?;
+        reject_connection_state(&cmd_args)?;
+        let cmd_args = options.prefix_keys(cmd_args);
 
-        if cmd_args.is_empty() {
-            continue;
-        }
-
-        // Special handling for commands with no arguments
-        if cmd_args.len() == 1 {
-            let cmd_name = cmd_args[0].to_uppercase();
-            if cmd_name == "MGET" {
+        // Special handling for commands with no arguments, RESP re-encoding gets the
+        // replies of Redis itself
+        if cmd_args.len() == 1 && options.format != ResponseFormat::Resp {
+            let cmd_name = &cmd_args[0];
+            if cmd_name.eq_ignore_ascii_case(b"MGET") {
                 // MGET with no arguments returns empty array
-                let empty_array = Value::Array(vec![]);
-                let encoded_empty = encode_result(&empty_array, encoding_enabled);
-                let response = json!([{ "result": encoded_empty }]);
-                return Ok(Json(response));
-            } else if cmd_name == "DEL" {
+                let result = BodyValue::object([("result", BodyValue::Array(vec![]))]);
+                return Ok(options.render(vec![result].into()));
+            } else if cmd_name.eq_ignore_ascii_case(b"DEL") {
                 // DEL with no arguments returns 0 (no keys deleted)
-                let zero = Value::Number(0.into());
-                let encoded_zero = encode_result(&zero, encoding_enabled);
-                let response = json!([{ "result": encoded_zero }]);
-                return Ok(Json(response));
+                let result = BodyValue::object([("result", 0.into())]);
+                return Ok(options.render(vec![result].into()));
             }
             // Other commands with no args - execute normally (Redis will handle it)
         }
 
-        let mut cmd = redis::cmd(&cmd_args[0]);
-        for arg in cmd_args.iter().skip(1) {
-            cmd.arg(arg);
-        }
+        let Some(cmd) = build_command(&cmd_args) else {
+            continue;
+        };
         pipeline.add_command(cmd);
         has_commands = true;
     }
 
     // If no commands were added (all were empty), return empty array
     if !has_commands {
-        let response = Value::Array(vec![]);
-        let encoded = encode_result(&response, encoding_enabled);
-        return Ok(Json(encoded));
+        return Ok(empty_response(options));
+    }
+
+    // Execute pipeline, on a replica if every command is read-only
+    let target = if options.resp3 {
+        Target::Resp3
+    } else {
+        pool.read_target(pipeline.cmd_iter(), options.read_primary)
+    };
+
+    // RESP re-encoding: one reply per command, error replies included
+    if options.format == ResponseFormat::Resp {
+        let values = pool.execute_pipeline_raw(&mut pipeline, target).await?;
+        let values = strip_keys(&pipeline, values, options);
+        return Ok(resp_response(&values, options.resp3));
     }
 
-    // Execute pipeline
     let results = pool
-        .execute_pipeline(&mut pipeline)
+        .execute_pipeline(&mut pipeline, target)
         .await
         .map_err(AppError::Redis);
 
-    match results {
-        Ok(redis_values) => {
-            let responses: Vec<Value> = redis_values
+    let response = match results {
+        Ok(redis_values) => BodyValue::Array(
+            strip_keys(&pipeline, redis_values, options)
                 .into_iter()
-                .map(|v| {
-                    let json_value = redis_value_to_json(v);
-                    json!({ "result": json_value })
-                })
-                .collect();
-
-            let response = Value::Array(responses);
-            let encoded = encode_result(&response, encoding_enabled);
-            Ok(Json(encoded))
-        }
+                .map(|v| BodyValue::object([("result", redis_value_to_body(v, options))]))
+                .collect(),
+        ),
         Err(e) => {
             let error_msg = e.to_string();
-            let response = json!({ "error": error_msg });
-            Ok(Json(response))
+            BodyValue::object([("error", error_msg.into())])
         }
+    };
+    Ok(options.render(response))
+}
+
+/// Remove the key prefix of the token from the replies
+fn strip_keys(
+    pipeline: &redis::Pipeline,
+    values: Vec<redis::Value>,
+    options: RequestOptions<'_>,
+) -> Vec<redis::Value> {
+    pipeline
+        .cmd_iter()
+        .zip(values)
+        .map(|(cmd, value)| options.strip_keys(command_name(cmd), value))
+        .collect()
+}
+
+fn empty_response(options: RequestOptions) -> Response {
+    match options.format {
+        ResponseFormat::Resp => resp_response(&[], options.resp3),
+        _ => options.render(BodyValue::Array(vec![])),
     }
 }
@@ -0,0 +1,59 @@
+use crate::client::{PubSubHub, Subscription, Topic};
+use crate::utils::options::RequestOptions;
+use crate::utils::AppError;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::convert::Infallible;
+
+/// Stream the messages of a channel or pattern as Server-Sent Events.
+///
+/// Events use the Upstash format: a first `subscribe,<channel>,1` (or
+/// `psubscribe,<pattern>,1`) confirmation, then one `message,<channel>,<payload>`
+/// (or `pmessage,<pattern>,<channel>,<payload>`) per published message. The
+/// subscription is released when the client disconnects and the stream is
+/// dropped.
+///
+/// The token policy applies as for `SUBSCRIBE`/`PSUBSCRIBE` commands, and the
+/// key prefix of the token is added to the channel or pattern. Events show the
+/// names without the prefix.
+pub async fn handle_subscribe_internal(
+    hub: PubSubHub,
+    topic: Topic,
+    options: RequestOptions<'_>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let (command, name) = match &topic {
+        Topic::Channel(channel) => ("SUBSCRIBE", channel.clone()),
+        Topic::Pattern(pattern) => ("PSUBSCRIBE", pattern.clone()),
+    };
+    let args = vec![command.as_bytes().to_vec(), name.clone().into_bytes()];
+    options.check_allowed(&args)?;
+    let prefixed = String::from_utf8_lossy(&options.prefix_keys(args)[1]).into_owned();
+    let prefix = options.key_prefix.unwrap_or_default().to_string();
+
+    let (confirmation, topic) = match topic {
+        Topic::Channel(_) => (format!("subscribe,{},1", name), Topic::Channel(prefixed)),
+        Topic::Pattern(_) => (format!("psubscribe,{},1", name), Topic::Pattern(prefixed)),
+    };
+    let subscription = hub.subscribe(topic).await;
+
+    let confirmation = stream::once(async move { Ok(Event::default().data(confirmation)) });
+    let messages = stream::unfold(subscription, move |mut subscription: Subscription| {
+        let (name, prefix) = (name.clone(), prefix.clone());
+        async move {
+            let message = subscription.recv().await?;
+            let channel = message
+                .channel
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(&message.channel);
+            let payload = String::from_utf8_lossy(&message.payload);
+            // A subscription only receives the messages of its own pattern
+            let data = match &message.pattern {
+                Some(_) => format!("pmessage,{},{},{}", name, channel, payload),
+                None => format!("message,{},{}", channel, payload),
+            };
+            Some((Ok(Event::default().data(data)), subscription))
+        }
+    });
+
+    Ok(Sse::new(confirmation.chain(messages)).keep_alive(KeepAlive::default()))
+}
@@ -1,176 +1,292 @@
-use crate::client::RedisPool;
-use crate::handlers::command::redis_value_to_json;
-use crate::utils::encoding::encode_result;
+use crate::client::{same_slot, RedisPool, Target, TransactionReplies, CROSSSLOT_ERROR};
+use crate::handlers::command::{redis_value_to_body, server_error_message};
+use crate::utils::body::BodyValue;
+use crate::utils::encoding::{build_command, command_args, command_name};
+use crate::utils::metrics;
+use crate::utils::options::{RequestOptions, ResponseFormat};
+use crate::utils::resp::resp_response;
 use crate::utils::AppError;
 use axum::extract::State;
-use axum::Json;
-use serde_json::{json, Value};
+use axum::response::Response;
 use std::time::Duration;
 use tokio::time::sleep;
 
-pub async fn handle_transaction_internal(
-    State(pool): State<RedisPool>,
-    Json(body): Json<Value>,
-    encoding_enabled: bool,
-) -> Result<Json<Value>, AppError> {
-    // Extract array of command arrays from body (same format as pipeline)
-    // Upstash sends directly as array: [[...]], but we also support {"_json": [[...]]}
-    let command_arrays = if body.is_array() {
-        // Body is directly an array: [[...]]
-        body.as_array().unwrap()
-    } else {
-        // Body has _json wrapper: {"_json": [[...]]}
-        body.get("_json")
+/// Error of a transaction aborted because a watched key changed
+const WATCH_ABORTED_ERROR: &str = "WATCHABORTED Transaction aborted, a watched key was modified";
+
+/// Transaction request: a bare command list, or an object adding WATCH keys
+/// and reads run between WATCH and MULTI
+struct TransactionRequest {
+    watch: Option<redis::Cmd>,
+    reads: Vec<redis::Cmd>,
+    cmds: Vec<redis::Cmd>,
+    /// Object requests get an object response with the read results
+    extended: bool,
+}
+
+impl TransactionRequest {
+    fn parse(body: &BodyValue, options: RequestOptions) -> Result<Self, AppError> {
+        // Upstash sends directly as array: [[...]], but we also support {"_json": [[...]]}
+        if let Some(commands) = body.as_array().or_else(|| body.get("_json")?.as_array()) {
+            return Ok(Self {
+                watch: None,
+                reads: Vec::new(),
+                cmds: parse_command_arrays(commands, options)?,
+                extended: false,
+            });
+        }
+
+        // {"watch": ["key", ...], "reads": [[...]], "commands": [[...]]}
+        let commands = body
+            .get("commands")
             .and_then(|v| v.as_array())
             .ok_or_else(|| {
                 AppError::MalformedRequest(
                     "Invalid command array. Expected an array of string arrays at root."
                         .to_string(),
                 )
-            })?
-    };
+            })?;
+
+        let watch = match body.get("watch") {
+            None => None,
+            Some(BodyValue::Array(keys)) if !keys.is_empty() && keys.iter().all(is_key) => {
+                let mut watch = vec![b"WATCH".to_vec()];
+                watch.extend(command_args(keys, options.base64_args)?);
+                build_command(&options.prefix_keys(watch))
+            }
+            Some(_) => {
+                return Err(AppError::MalformedRequest(
+                    "Invalid watch list. Expected a non-empty array of key names.".to_string(),
+                ))
+            }
+        };
+
+        let reads = match body.get("reads") {
+            None => Vec::new(),
+            Some(BodyValue::Array(reads)) => parse_command_arrays(reads, options)?,
+            Some(_) => {
+                return Err(AppError::MalformedRequest(
+                    "Invalid reads. Expected an array of string arrays.".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            watch,
+            reads,
+            cmds: parse_command_arrays(commands, options)?,
+            extended: true,
+        })
+    }
 
-    // Validate all items are arrays
+    fn all_commands(&self) -> impl Iterator<Item = &redis::Cmd> {
+        self.watch.iter().chain(&self.reads).chain(&self.cmds)
+    }
+}
+
+fn parse_command_arrays(
+    command_arrays: &[BodyValue],
+    options: RequestOptions<'_>,
+) -> Result<Vec<redis::Cmd>, AppError> {
+    let mut cmds = Vec::with_capacity(command_arrays.len());
     for cmd_array in command_arrays {
-        if !cmd_array.is_array() {
-            return Err(AppError::MalformedRequest(
+        // Validate all items are arrays
+        let cmd_array = cmd_array.as_array().ok_or_else(|| {
+            AppError::MalformedRequest(
                 "Invalid command array. Expected an array of string arrays at root.".to_string(),
-            ));
-        }
+            )
+        })?;
+        let cmd_args = command_args(cmd_array, options.base64_args)?;
+        options.check_allowed(&cmd_args)?;
+        // Empty arrays are skipped
+        cmds.extend(build_command(&options.prefix_keys(cmd_args)));
     }
+    Ok(cmds)
+}
+
+pub async fn handle_transaction_internal(
+    State(pool): State<RedisPool>,
+    body: BodyValue,
+    options: RequestOptions<'_>,
+) -> Result<Response, AppError> {
+    let mut request = TransactionRequest::parse(&body, options)?;
+    request.reads = pool.prepare_batch(&request.reads);
+    request.cmds = pool.prepare_batch(&request.cmds);
 
-    // Transactions need retry logic too, but we must retry the entire transaction
+    // In a cluster, the whole transaction must run on the node owning its keys
+    if pool.is_cluster() && !same_slot(request.all_commands()) {
+        return Err(AppError::MalformedRequest(CROSSSLOT_ERROR.to_string()));
+    }
+
+    // Only getting a connection is retried. Once WATCH, the reads or the
+    // MULTI/EXEC block have been written, a connection error does not tell
+    // whether Redis ran them, and running the transaction again could apply
+    // its writes twice.
+    // WATCH, MULTI, the commands and EXEC all go through a connection checked
+    // out for this request only, so nothing can interleave with them.
     let max_retry = pool.max_retry();
+    let target = if options.resp3 {
+        Target::Resp3
+    } else {
+        Target::Primary
+    };
     let mut attempt = 1u32;
 
     loop {
-        match pool.get_connection().await {
+        let mut replies = match pool.checkout(target).await {
             Ok(mut conn) => {
-                // Execute MULTI
-                match redis::cmd("MULTI")
-                    .query_async::<_, String>(&mut conn)
+                conn.transaction(request.watch.as_ref(), &request.reads, &request.cmds)
                     .await
-                {
-                    Ok(_) => {
-                        // Execute all commands in transaction
-                        let mut transaction_failed = false;
-                        for cmd_array in command_arrays {
-                            let cmd_args: Vec<String> = cmd_array
-                                .as_array()
-                                .unwrap()
-                                .iter()
-                                .map(|v| match v {
-                                    Value::String(s) => s.clone(),
-                                    Value::Number(n) => n.to_string(),
-                                    Value::Bool(b) => b.to_string(),
-                                    Value::Null => "".to_string(),
-                                    _ => v.to_string(),
-                                })
-                                .collect();
-
-                            if cmd_args.is_empty() {
-                                continue;
-                            }
-
-                            let mut cmd = redis::cmd(&cmd_args[0]);
-                            for arg in cmd_args.iter().skip(1) {
-                                cmd.arg(arg);
-                            }
-
-                            match cmd.query_async::<_, String>(&mut conn).await {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    // Check if it's a connection error
-                                    if matches!(e.kind(), redis::ErrorKind::IoError) {
-                                        transaction_failed = true;
-                                        break;
-                                    }
-                                    // For other errors during transaction, continue
-                                }
-                            }
-                        }
-
-                        if transaction_failed {
-                            if max_retry == -1 || attempt < max_retry as u32 {
-                                attempt += 1;
-                                sleep(Duration::from_secs(1) * (attempt - 1)).await;
-                                continue;
-                            } else {
-                                break;
-                            }
-                        }
-
-                        // Execute EXEC
-                        match redis::cmd("EXEC")
-                            .query_async::<_, Vec<redis::Value>>(&mut conn)
-                            .await
-                        {
-                            Ok(redis_values) => {
-                                let responses: Vec<Value> = redis_values
-                                    .into_iter()
-                                    .map(|v| {
-                                        let json_value = redis_value_to_json(v);
-                                        json!({ "result": json_value })
-                                    })
-                                    .collect();
-
-                                let response = Value::Array(responses);
-                                let encoded = encode_result(&response, encoding_enabled);
-                                return Ok(Json(encoded));
-                            }
-                            Err(e) => {
-                                // Check if it's a connection error
-                                if matches!(e.kind(), redis::ErrorKind::IoError) {
-                                    if max_retry == -1 || attempt < max_retry as u32 {
-                                        attempt += 1;
-                                        sleep(Duration::from_secs(1) * (attempt - 1)).await;
-                                        continue;
-                                    } else {
-                                        break;
-                                    }
-                                }
-                                let error_msg = e.to_string();
-                                let response = json!({ "error": error_msg });
-                                return Ok(Json(response));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        // Check if it's a connection error
-                        if matches!(e.kind(), redis::ErrorKind::IoError) {
-                            if max_retry == -1 || attempt < max_retry as u32 {
-                                attempt += 1;
-                                sleep(Duration::from_secs(1) * (attempt - 1)).await;
-                                continue;
-                            } else {
-                                break;
-                            }
-                        }
-                        let error_msg = AppError::Redis(e).to_string();
-                        let response = json!({ "error": error_msg });
-                        return Ok(Json(response));
-                    }
-                }
+            }
+            // Once the retries are exhausted, the last error is returned
+            Err(e)
+                if matches!(e.kind(), redis::ErrorKind::IoError)
+                    && (max_retry == -1 || attempt < max_retry as u32) =>
+            {
+                metrics::record_error(&e);
+                attempt += 1;
+                metrics::record_retry("transaction");
+                sleep(Duration::from_secs(1) * (attempt - 1)).await;
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+
+        match &mut replies {
+            Ok(replies) => {
+                record_commands(&request, replies);
+                strip_keys(&request, replies, options);
+            }
+            Err(e) => metrics::record_error(e),
+        }
+
+        match replies {
+            // RESP re-encoding: the replies of the reads, MULTI, each queued command
+            // and EXEC, in the order Redis sent them
+            Ok(replies) if options.format == ResponseFormat::Resp => {
+                let values: Vec<redis::Value> = replies
+                    .reads
+                    .into_iter()
+                    .chain([replies.multi])
+                    .chain(replies.queued)
+                    .chain([replies.exec])
+                    .collect();
+                return Ok(resp_response(&values, options.resp3));
+            }
+            Ok(replies) => {
+                return transaction_response(replies, request.extended, options)
+                    .map(|response| options.render(response))
             }
             Err(e) => {
-                // Connection error, retry
-                if matches!(e.kind(), redis::ErrorKind::IoError) {
-                    if max_retry == -1 || attempt < max_retry as u32 {
-                        attempt += 1;
-                        sleep(Duration::from_secs(1) * (attempt - 1)).await;
-                        continue;
-                    } else {
-                        break;
-                    }
-                }
                 let error_msg = AppError::Redis(e).to_string();
-                let response = json!({ "error": error_msg });
-                return Ok(Json(response));
+                return Ok(options.render(BodyValue::object([("error", error_msg.into())])));
             }
         }
     }
+}
+
+/// Turn the transaction replies into the Upstash response: one
+/// `{"result": ...}` or `{"error": ...}` entry per command, or a top-level
+/// error when the transaction was aborted before running.
+///
+/// Extended requests get `{"reads": [...], "exec": [...]}`.
+fn transaction_response(
+    replies: TransactionReplies,
+    extended: bool,
+    options: RequestOptions<'_>,
+) -> Result<BodyValue, AppError> {
+    let reads = reply_entries(replies.reads, options);
+
+    // MULTI itself can be refused, e.g. when nested
+    if let Some(error) = server_error_message(&replies.multi) {
+        return Err(AppError::TransactionAborted(error));
+    }
+
+    let results = match replies.exec {
+        redis::Value::Array(values) => reply_entries(values, options),
+        // A watched key changed, nothing was run and the client may retry
+        redis::Value::Nil => {
+            return Err(AppError::WatchAborted {
+                message: WATCH_ABORTED_ERROR.to_string(),
+                reads,
+            })
+        }
+        // EXECABORT: a command could not be queued, nothing was run
+        exec => {
+            let exec_error = server_error_message(&exec)
+                .unwrap_or_else(|| "EXECABORT Transaction discarded".to_string());
+            let error = match replies.queued.iter().find_map(server_error_message) {
+                Some(queue_error) => {
+                    format!("{}: {}", exec_error.trim_end_matches('.'), queue_error)
+                }
+                None => exec_error,
+            };
+            return Err(AppError::TransactionAborted(error));
+        }
+    };
+
+    if extended {
+        Ok(BodyValue::object([
+            ("reads", reads.into()),
+            ("exec", results.into()),
+        ]))
+    } else {
+        Ok(results.into())
+    }
+}
+
+/// Count the commands of the transaction with their result, or their
+/// queueing reply when they did not run
+fn record_commands(request: &TransactionRequest, replies: &TransactionReplies) {
+    for (cmd, value) in request.reads.iter().zip(&replies.reads) {
+        metrics::record_command(cmd, value);
+    }
+    let results = match &replies.exec {
+        redis::Value::Array(results) => results.as_slice(),
+        _ => &[],
+    };
+    for (i, (cmd, queued)) in request.cmds.iter().zip(&replies.queued).enumerate() {
+        let value = match queued {
+            redis::Value::ServerError(_) => queued,
+            _ => results.get(i).unwrap_or(queued),
+        };
+        metrics::record_command(cmd, value);
+    }
+}
+
+/// Remove the key prefix of the token from the replies of the reads and of
+/// the queued commands
+fn strip_keys(
+    request: &TransactionRequest,
+    replies: &mut TransactionReplies,
+    options: RequestOptions<'_>,
+) {
+    let strip = |cmds: &[redis::Cmd], values: &mut Vec<redis::Value>| {
+        *values = cmds
+            .iter()
+            .zip(values.drain(..))
+            .map(|(cmd, value)| options.strip_keys(command_name(cmd), value))
+            .collect();
+    };
+    strip(&request.reads, &mut replies.reads);
+    if let redis::Value::Array(exec) = &mut replies.exec {
+        strip(&request.cmds, exec);
+    }
+}
+
+/// One `{"result": ...}` or `{"error": ...}` entry per reply
+fn reply_entries(values: Vec<redis::Value>, options: RequestOptions) -> Vec<BodyValue> {
+    values
+        .into_iter()
+        .map(|v| match server_error_message(&v) {
+            // Errors are never base64 encoded
+            Some(error) => BodyValue::object([("error", error.into())]),
+            None => BodyValue::object([("result", redis_value_to_body(v, options))]),
+        })
+        .collect()
+}
 
-    // Fallback error
-    let response = json!({ "error": "Transaction failed after retries" });
-    Ok(Json(response))
+/// WATCH keys are strings, or byte strings in MessagePack and CBOR bodies
+fn is_key(key: &BodyValue) -> bool {
+    matches!(key, BodyValue::String(_) | BodyValue::Bytes(_))
 }
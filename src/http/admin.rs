@@ -0,0 +1,236 @@
+use super::routes::AppState;
+use crate::client::RedisPool;
+use crate::config::Config;
+use crate::console::{Console, Status};
+use crate::utils::auth::extract_bearer_token;
+use crate::utils::AppError;
+use axum::{
+    body::Bytes,
+    extract::{Path, Request, State},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Log filter of the running server, replaced from the admin API
+pub type LogFilter = reload::Handle<EnvFilter, Registry>;
+
+/// Maintenance mode: every route but `/health` and `/metrics` answers 503
+/// while enabled
+#[derive(Clone, Default)]
+pub struct Maintenance(Arc<AtomicBool>);
+
+impl Maintenance {
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminState {
+    pub pool: RedisPool,
+    pub config: Config,
+    pub console: Console,
+    pub maintenance: Maintenance,
+    pub log_filter: LogFilter,
+}
+
+#[derive(Deserialize)]
+struct MaintenanceRequest {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct LogLevelRequest {
+    level: String,
+}
+
+/// Middleware refusing command requests in maintenance mode
+pub async fn maintenance(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if state.maintenance.is_enabled() && !matches!(path, "/health" | "/metrics") {
+        return AppError::Unavailable("Server is under maintenance, retry later".to_string())
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Every admin route requires SLASHLESS_ADMIN_TOKEN, API tokens are refused
+async fn require_admin_token(
+    State(state): State<AdminState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = extract_bearer_token(&request)?;
+    let admin = state.config.admin.as_ref().ok_or(AppError::Unauthorized)?;
+    if token.as_deref() != Some(admin.token.as_str()) {
+        tracing::warn!("Refused admin request to {}", request.uri().path());
+        return Err(AppError::Unauthorized);
+    }
+    Ok(next.run(request).await)
+}
+
+/// Parse the JSON body of an admin request
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, AppError> {
+    serde_json::from_slice(body)
+        .map_err(|e| AppError::MalformedRequest(format!("Invalid JSON body: {}", e)))
+}
+
+async fn status(State(state): State<AdminState>) -> Json<serde_json::Value> {
+    let log_level = state.log_filter.with_current(|filter| filter.to_string());
+    Json(json!({
+        "result": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "maintenance": state.maintenance.is_enabled(),
+            "log_level": log_level.ok(),
+            "redis": {
+                "address": state.config.redis_address(),
+                "reconnecting": state.pool.is_reconnecting(),
+                "max_retry": state.pool.max_retry(),
+                "pool": state.pool.stats(),
+            },
+        }
+    }))
+}
+
+/// Named tokens without their secrets
+async fn list_tokens(State(state): State<AdminState>) -> Json<serde_json::Value> {
+    let tokens: Vec<_> = state
+        .config
+        .tokens
+        .iter()
+        .map(|api_token| {
+            json!({
+                "name": api_token.name,
+                "scope": api_token.scope,
+                "expires_at": api_token.expires_at,
+                "expired": api_token.is_expired(),
+                "revoked": api_token.is_revoked(),
+                "prefix": api_token.prefix,
+                "rate_limit": api_token.rate_limit.map(|limit| limit.to_string()),
+            })
+        })
+        .collect();
+    Json(json!({ "result": tokens }))
+}
+
+/// Refuse a token until the server restarts
+async fn revoke_token(
+    State(state): State<AdminState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let api_token = state
+        .config
+        .tokens
+        .iter()
+        .find(|api_token| api_token.name == name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown token {}", name)))?;
+
+    api_token.revoke();
+    let _ = state.console.log_warn(format!("Token {} revoked", name));
+    Ok(Json(json!({ "result": "OK" })))
+}
+
+/// Drop every Redis connection and connect again
+async fn reconnect(State(state): State<AdminState>) -> Result<Json<serde_json::Value>, AppError> {
+    let _ = state
+        .console
+        .log_info("Reconnecting to Redis (admin request)".to_string());
+    let pong = state
+        .pool
+        .reconnect()
+        .await
+        .map_err(|e| AppError::ConnectionError(format!("Failed to reconnect: {}", e)))?;
+    Ok(Json(json!({ "result": pong })))
+}
+
+async fn set_maintenance(
+    State(state): State<AdminState>,
+    body: Bytes,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let MaintenanceRequest { enabled } = parse_body(&body)?;
+
+    state.maintenance.set(enabled);
+    if enabled {
+        let _ = state.console.update_server_status(Status::Maintenance);
+        let _ = state
+            .console
+            .log_warn("Maintenance mode enabled".to_string());
+    } else {
+        let _ = state.console.update_server_status(Status::Ready);
+        let _ = state
+            .console
+            .log_info("Maintenance mode disabled".to_string());
+    }
+    Ok(Json(json!({ "result": { "maintenance": enabled } })))
+}
+
+/// Replace the log filter, with the RUST_LOG syntax (`debug`,
+/// `info,stashless=trace`, ...)
+async fn set_log_level(
+    State(state): State<AdminState>,
+    body: Bytes,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let LogLevelRequest { level } = parse_body(&body)?;
+
+    let filter = EnvFilter::try_new(&level)
+        .map_err(|e| AppError::MalformedRequest(format!("Invalid log level: {}", e)))?;
+    state
+        .log_filter
+        .reload(filter)
+        .map_err(|e| AppError::ServerError(format!("Failed to change the log level: {}", e)))?;
+    let _ = state
+        .console
+        .log_info(format!("Log level changed to {}", level));
+    Ok(Json(json!({ "result": { "log_level": level } })))
+}
+
+pub fn create_admin_router(state: AdminState) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/tokens", get(list_tokens))
+        .route("/tokens/:name", delete(revoke_token))
+        .route("/reconnect", post(reconnect))
+        .route("/maintenance", put(set_maintenance))
+        .route("/log-level", put(set_log_level))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_admin_token,
+        ))
+        .with_state(state)
+}
+
+/// Serve the admin API on its own listener, over HTTPS when the main listener
+/// uses it. The task ends with the process.
+pub fn spawn_admin_server(
+    listener: TcpListener,
+    router: Router,
+    tls_config: Option<RustlsConfig>,
+    console: Console,
+) {
+    tokio::spawn(async move {
+        let result = match tls_config {
+            Some(tls_config) => {
+                super::tls::serve_tls(listener, router, tls_config, std::future::pending()).await
+            }
+            None => axum::serve(listener, router)
+                .await
+                .map_err(|e| AppError::ServerError(format!("Server error: {}", e))),
+        };
+        if let Err(e) = result {
+            let _ = console.log_error(format!("Admin API stopped: {}", e));
+        }
+    });
+}
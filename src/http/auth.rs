@@ -0,0 +1,60 @@
+use super::routes::AppState;
+use crate::utils::auth::{extract_bearer_token, validate_token, Caller};
+use crate::utils::AppError;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Outcome of the bearer token check, stored in the request extensions
+#[derive(Clone)]
+struct Authentication(Result<Caller, AuthFailure>);
+
+#[derive(Clone)]
+enum AuthFailure {
+    Unauthorized,
+    Malformed(String),
+}
+
+impl From<AppError> for AuthFailure {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::MalformedRequest(message) => AuthFailure::Malformed(message),
+            _ => AuthFailure::Unauthorized,
+        }
+    }
+}
+
+impl From<AuthFailure> for AppError {
+    fn from(failure: AuthFailure) -> Self {
+        match failure {
+            AuthFailure::Unauthorized => AppError::Unauthorized,
+            AuthFailure::Malformed(message) => AppError::MalformedRequest(message),
+        }
+    }
+}
+
+/// Middleware checking the bearer token once per request, so a JWT is only
+/// verified once.
+///
+/// Requests are never refused here: rate limiting runs next and has to count
+/// requests with an invalid token too. Handlers get the outcome with
+/// `request_caller`.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let outcome = extract_bearer_token(&request)
+        .and_then(|token| validate_token(token.as_deref(), &state.config))
+        .map_err(AuthFailure::from);
+    request.extensions_mut().insert(Authentication(outcome));
+    next.run(request).await
+}
+
+/// Caller of a request, as authenticated by `authenticate`
+pub fn request_caller(request: &Request) -> Result<Caller, AppError> {
+    match request.extensions().get::<Authentication>() {
+        Some(Authentication(outcome)) => outcome.clone().map_err(AppError::from),
+        None => Err(AppError::Unauthorized),
+    }
+}
@@ -0,0 +1,36 @@
+use crate::utils::body::BodyFormat;
+use crate::utils::errors::ErrorBody;
+use crate::utils::options::{RequestOptions, ResponseFormat};
+use axum::extract::Request;
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Middleware encoding error responses like the other responses.
+///
+/// Errors are built as JSON, wherever they are raised (authentication, rate
+/// limits, handlers). When the client asked for MessagePack or CBOR, the body
+/// is encoded again in that format, keeping the status and the other headers
+/// (`Retry-After`, ...). Clients of the RESP re-encoding still get JSON errors.
+pub async fn encode_errors(request: Request, next: Next) -> Response {
+    let format = RequestOptions::from_request(&request).format;
+    let response = next.run(request).await;
+
+    let format = match format {
+        ResponseFormat::Body(format) if format != BodyFormat::Json => format,
+        _ => return response,
+    };
+    let Some(ErrorBody(body)) = response.extensions().get::<ErrorBody>().cloned() else {
+        return response;
+    };
+
+    let (parts, _) = response.into_parts();
+    let mut encoded = body.render(format);
+    *encoded.status_mut() = parts.status;
+    for (name, value) in parts.headers.iter() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            encoded.headers_mut().insert(name, value.clone());
+        }
+    }
+    encoded
+}
@@ -0,0 +1,43 @@
+use super::auth::request_caller;
+use super::routes::AppState;
+use crate::utils::metrics;
+use crate::utils::AppError;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Instant;
+
+/// Content type of the Prometheus text format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Middleware counting requests and their latency per route.
+///
+/// Routes are labelled by their pattern (`/*command` for path-style
+/// commands), so the label set stays bounded.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    metrics::record_request(&endpoint, response.status().as_u16(), started.elapsed());
+    response
+}
+
+/// Prometheus scrape endpoint, authenticated like the command routes
+pub async fn metrics_with_auth(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<Response, AppError> {
+    request_caller(&request)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+        metrics::render(state.pool.stats()),
+    )
+        .into_response())
+}
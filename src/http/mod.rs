@@ -1,2 +1,8 @@
+pub mod admin;
+pub mod auth;
+pub mod errors;
+pub mod metrics;
+pub mod rate_limit;
 pub mod routes;
 pub mod server;
+pub mod tls;
@@ -0,0 +1,248 @@
+use super::auth::request_caller;
+use super::routes::AppState;
+use crate::config::{RateLimit, RateLimitConfig};
+use crate::utils::auth::Caller;
+use crate::utils::metrics;
+use crate::utils::AppError;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+
+/// How often the per-IP and per-token buckets are scanned for idle ones
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Token buckets for the global, per-IP and per-token rate limits
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<Buckets>>,
+    limited: Arc<AtomicU64>,
+}
+
+struct Buckets {
+    global: Option<Bucket>,
+    ips: HashMap<IpAddr, Bucket>,
+    tokens: HashMap<String, Bucket>,
+    pruned: Instant,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    limit: RateLimit,
+    available: f64,
+    /// Last refill, which happens each time the bucket is used
+    updated: Instant,
+}
+
+/// State of the most constrained bucket of a request, sent back as headers
+struct Quota {
+    limit: RateLimit,
+    remaining: f64,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            available: limit.requests as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.available =
+            (self.available + elapsed * rate(self.limit)).min(self.limit.requests as f64);
+        self.updated = now;
+    }
+
+    /// Whether the bucket refilled completely since it was last used, so
+    /// dropping it changes nothing
+    fn is_idle(&self, now: Instant) -> bool {
+        now.duration_since(self.updated) >= self.limit.period
+    }
+}
+
+impl Quota {
+    /// Seconds until one request is available again
+    fn retry_after(&self) -> u64 {
+        ((1.0 - self.remaining) / rate(self.limit)).ceil().max(1.0) as u64
+    }
+
+    /// Seconds until the bucket is full again
+    fn reset(&self) -> u64 {
+        ((self.limit.requests as f64 - self.remaining) / rate(self.limit)).ceil() as u64
+    }
+
+    fn write_headers(&self, headers: &mut HeaderMap) {
+        let remaining = self.remaining.max(0.0).floor() as u64;
+        for (name, value) in [
+            ("x-ratelimit-limit", self.limit.requests as u64),
+            ("x-ratelimit-remaining", remaining),
+            ("x-ratelimit-reset", self.reset()),
+        ] {
+            headers.insert(name, HeaderValue::from(value));
+        }
+    }
+}
+
+/// Requests per second of a limit
+fn rate(limit: RateLimit) -> f64 {
+    limit.requests as f64 / limit.period.as_secs_f64()
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(Buckets {
+                global: None,
+                ips: HashMap::new(),
+                tokens: HashMap::new(),
+                pruned: Instant::now(),
+            })),
+            limited: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Requests refused since startup
+    pub fn limited(&self) -> u64 {
+        self.limited.load(Ordering::Relaxed)
+    }
+
+    /// Take one request from every bucket applying to the request, or none
+    /// of them when one is empty.
+    ///
+    /// Returns the most constrained bucket, `None` when nothing is limited.
+    fn acquire(
+        &self,
+        ip: Option<IpAddr>,
+        token: Option<(&str, RateLimit)>,
+    ) -> Result<Option<Quota>, Quota> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets {
+            global,
+            ips,
+            tokens,
+            pruned,
+        } = &mut *buckets;
+
+        // Scanning every bucket is amortized over the requests of an interval
+        if now.duration_since(*pruned) >= PRUNE_INTERVAL {
+            ips.retain(|_, bucket| !bucket.is_idle(now));
+            tokens.retain(|_, bucket| !bucket.is_idle(now));
+            *pruned = now;
+        }
+
+        let mut applied: Vec<&mut Bucket> = Vec::with_capacity(3);
+        if let Some(limit) = self.config.global {
+            applied.push(global.get_or_insert_with(|| Bucket::full(limit, now)));
+        }
+        if let (Some(limit), Some(ip)) = (self.config.per_ip, ip) {
+            applied.push(ips.entry(ip).or_insert_with(|| Bucket::full(limit, now)));
+        }
+        if let Some((key, limit)) = token {
+            let bucket = tokens
+                .entry(key.to_string())
+                .or_insert_with(|| Bucket::full(limit, now));
+            applied.push(bucket);
+        }
+
+        for bucket in applied.iter_mut() {
+            bucket.refill(now);
+        }
+
+        // Most constrained bucket once the request is counted, relative to
+        // its size
+        let tightest = applied
+            .iter()
+            .map(|bucket| Quota {
+                limit: bucket.limit,
+                remaining: bucket.available,
+            })
+            .min_by(|a, b| {
+                let level = |quota: &Quota| (quota.remaining - 1.0) / quota.limit.requests as f64;
+                level(a).total_cmp(&level(b))
+            });
+
+        if let Some(quota) = tightest.as_ref().filter(|quota| quota.remaining < 1.0) {
+            self.limited.fetch_add(1, Ordering::Relaxed);
+            metrics::record_rate_limited();
+            return Err(Quota {
+                limit: quota.limit,
+                remaining: quota.remaining,
+            });
+        }
+
+        for bucket in applied {
+            bucket.available -= 1.0;
+        }
+        Ok(tightest.map(|quota| Quota {
+            remaining: quota.remaining - 1.0,
+            ..quota
+        }))
+    }
+}
+
+/// Middleware refusing requests over the global, per-IP or per-token limit
+/// with `429 Too Many Requests`.
+///
+/// Requests with an invalid token only count against the global and per-IP
+/// limits, the handler rejects them afterwards.
+pub async fn rate_limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if matches!(request.uri().path(), "/health" | "/metrics") {
+        return next.run(request).await;
+    }
+
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    // Authenticated by the previous middleware
+    let caller = request_caller(&request).ok();
+    let token = caller.as_ref().and_then(|caller| {
+        let limit = match caller {
+            Caller::Token(api_token) => api_token.rate_limit,
+            _ => None,
+        };
+        Some((
+            caller.rate_key()?,
+            limit.or(state.config.rate_limit.per_token)?,
+        ))
+    });
+
+    match state.rate_limiter.acquire(ip, token) {
+        Ok(quota) => {
+            let mut response = next.run(request).await;
+            if let Some(quota) = quota {
+                quota.write_headers(response.headers_mut());
+            }
+            response
+        }
+        Err(quota) => {
+            if let Some(name) = caller.as_ref().and_then(Caller::name) {
+                tracing::warn!("Rate limited token {}", name);
+            }
+            let mut response = AppError::RateLimited(format!(
+                "Rate limit exceeded, retry in {} seconds",
+                quota.retry_after()
+            ))
+            .into_response();
+            quota.write_headers(response.headers_mut());
+            response
+                .headers_mut()
+                .insert("retry-after", HeaderValue::from(quota.retry_after()));
+            response
+        }
+    }
+}
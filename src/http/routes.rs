@@ -1,11 +1,20 @@
-use crate::client::RedisPool;
+use crate::client::{PubSubHub, RedisPool, Topic};
 use crate::config::Config;
-use crate::handlers::{command, pipeline, transaction};
-use crate::utils::auth::{check_encoding_header, extract_bearer_token, validate_token};
+use crate::handlers::{command, path, pipeline, subscribe, transaction};
+use crate::http::admin::{maintenance, Maintenance};
+use crate::http::auth::{authenticate, request_caller};
+use crate::http::errors::encode_errors;
+use crate::http::metrics::{metrics_with_auth, track_requests};
+use crate::http::rate_limit::{rate_limit, RateLimiter};
+use crate::utils::auth::Caller;
+use crate::utils::body::BodyValue;
+use crate::utils::options::RequestOptions;
 use crate::utils::AppError;
 use axum::{
     body::Body,
-    extract::{Request, State},
+    extract::{Path, Query, Request, State},
+    middleware,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
@@ -14,7 +23,10 @@ use serde_json::json;
 #[derive(Clone)]
 pub struct AppState {
     pub pool: RedisPool,
+    pub pubsub: PubSubHub,
     pub config: Config,
+    pub rate_limiter: RateLimiter,
+    pub maintenance: Maintenance,
 }
 
 async fn root() -> Json<serde_json::Value> {
@@ -25,75 +37,191 @@ async fn health() -> Json<serde_json::Value> {
     Json(json!({"status": "ok"}))
 }
 
+/// Options of a command request, carrying the command policy of the caller
+fn command_options<'a>(request: &Request<Body>, caller: &'a Caller) -> RequestOptions<'a> {
+    // Check encoding, read preference, protocol and body format headers
+    RequestOptions {
+        policy: Some(caller.policy()),
+        key_prefix: caller.key_prefix(),
+        ..RequestOptions::from_request(request)
+    }
+}
+
+/// Commands refused by a token policy are logged with the token name
+fn log_refused(error: &AppError, caller: &Caller) {
+    if let AppError::Forbidden(message) = error {
+        match caller.name() {
+            Some(name) => tracing::warn!("Token {}: {}", name, message),
+            None => tracing::warn!("{}", message),
+        }
+    }
+}
+
 async fn handle_command_with_auth(
     State(state): State<AppState>,
     request: Request<Body>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    // Extract and validate token
-    let token = extract_bearer_token(&request)?;
-    validate_token(token.as_deref(), &state.config)?;
-
-    // Check encoding header
-    let encoding_enabled = check_encoding_header(&request);
+) -> Result<Response, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
 
     // Extract body
     let body_bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
         .await
         .map_err(|_| AppError::MalformedRequest("Failed to read request body".to_string()))?;
-    let body: serde_json::Value = serde_json::from_slice(&body_bytes)
-        .map_err(|_| AppError::MalformedRequest("Invalid JSON body".to_string()))?;
+    let body = BodyValue::decode(&body_bytes, options.body_format)?;
 
-    command::handle_command_internal(State(state.pool), Json(body), encoding_enabled).await
+    command::handle_command_internal(State(state.pool), body, options)
+        .await
+        .inspect_err(|e| log_refused(e, &caller))
 }
 
 async fn handle_pipeline_with_auth(
     State(state): State<AppState>,
     request: Request<Body>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    // Extract and validate token
-    let token = extract_bearer_token(&request)?;
-    validate_token(token.as_deref(), &state.config)?;
-
-    // Check encoding header
-    let encoding_enabled = check_encoding_header(&request);
+) -> Result<Response, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
 
     // Extract body
     let body_bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
         .await
         .map_err(|_| AppError::MalformedRequest("Failed to read request body".to_string()))?;
-    let body: serde_json::Value = serde_json::from_slice(&body_bytes)
-        .map_err(|_| AppError::MalformedRequest("Invalid JSON body".to_string()))?;
+    let body = BodyValue::decode(&body_bytes, options.body_format)?;
 
-    pipeline::handle_pipeline_internal(State(state.pool), Json(body), encoding_enabled).await
+    pipeline::handle_pipeline_internal(State(state.pool), body, options)
+        .await
+        .inspect_err(|e| log_refused(e, &caller))
 }
 
 async fn handle_transaction_with_auth(
     State(state): State<AppState>,
     request: Request<Body>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    // Extract and validate token
-    let token = extract_bearer_token(&request)?;
-    validate_token(token.as_deref(), &state.config)?;
-
-    // Check encoding header
-    let encoding_enabled = check_encoding_header(&request);
+) -> Result<Response, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
 
     // Extract body
     let body_bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
         .await
         .map_err(|_| AppError::MalformedRequest("Failed to read request body".to_string()))?;
-    let body: serde_json::Value = serde_json::from_slice(&body_bytes)
-        .map_err(|_| AppError::MalformedRequest("Invalid JSON body".to_string()))?;
+    let body = BodyValue::decode(&body_bytes, options.body_format)?;
+
+    transaction::handle_transaction_internal(State(state.pool), body, options)
+        .await
+        .inspect_err(|e| log_refused(e, &caller))
+}
+
+/// Upstash path-style commands: `GET /get/foo`, `POST /set/foo` with the value
+/// as the body
+async fn handle_path_command_with_auth(
+    State(state): State<AppState>,
+    request: Request<Body>,
+) -> Result<Response, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
+
+    // Query string options, in order
+    let Query(query) = Query::<Vec<(String, String)>>::try_from_uri(request.uri())
+        .map_err(|_| AppError::MalformedRequest("Invalid query string".to_string()))?;
+    let uri_path = request.uri().path().to_string();
+
+    // Extract raw body, used as the last argument
+    let body_bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
+        .await
+        .map_err(|_| AppError::MalformedRequest("Failed to read request body".to_string()))?;
 
-    transaction::handle_transaction_internal(State(state.pool), Json(body), encoding_enabled).await
+    let args = path::path_command_args(&uri_path, &query, &body_bytes)?;
+    path::handle_path_command_internal(State(state.pool), args, options)
+        .await
+        .inspect_err(|e| log_refused(e, &caller))
 }
 
-pub fn create_router(pool: RedisPool, config: Config) -> Router {
+async fn handle_subscribe_with_auth(
+    State(state): State<AppState>,
+    Path(channel): Path<String>,
+    request: Request<Body>,
+) -> Result<impl IntoResponse, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
+
+    subscribe::handle_subscribe_internal(state.pubsub, Topic::Channel(channel), options)
+        .await
+        .inspect_err(|e| log_refused(e, &caller))
+}
+
+async fn handle_psubscribe_with_auth(
+    State(state): State<AppState>,
+    Path(pattern): Path<String>,
+    request: Request<Body>,
+) -> Result<impl IntoResponse, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
+
+    subscribe::handle_subscribe_internal(state.pubsub, Topic::Pattern(pattern), options)
+        .await
+        .inspect_err(|e| log_refused(e, &caller))
+}
+
+/// SHA1 of the Lua scripts cached by the proxy, for tokens allowed to manage
+/// scripts (`SCRIPT`)
+async fn list_scripts_with_auth(
+    State(state): State<AppState>,
+    request: Request<Body>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    // Caller authenticated by the middleware, then the option headers
+    let caller = request_caller(&request)?;
+    let options = command_options(&request, &caller);
+    options
+        .check_allowed(&[b"SCRIPT".to_vec()])
+        .inspect_err(|e| log_refused(e, &caller))?;
+
+    Ok(Json(json!({ "result": state.pool.script_shas() })))
+}
+
+pub fn create_router(
+    pool: RedisPool,
+    config: Config,
+    rate_limiter: RateLimiter,
+    maintenance_mode: Maintenance,
+) -> Router {
+    let state = AppState {
+        pubsub: PubSubHub::new(pool.clone()),
+        pool,
+        config,
+        rate_limiter,
+        maintenance: maintenance_mode,
+    };
+
     Router::new()
         .route("/", get(root))
         .route("/", post(handle_command_with_auth))
         .route("/health", get(health))
+        .route("/metrics", get(metrics_with_auth))
         .route("/pipeline", post(handle_pipeline_with_auth))
         .route("/multi-exec", post(handle_transaction_with_auth))
-        .with_state(AppState { pool, config })
+        .route("/scripts", get(list_scripts_with_auth))
+        .route(
+            "/subscribe/:channel",
+            get(handle_subscribe_with_auth).post(handle_subscribe_with_auth),
+        )
+        .route(
+            "/psubscribe/:pattern",
+            get(handle_psubscribe_with_auth).post(handle_psubscribe_with_auth),
+        )
+        .route(
+            "/*command",
+            get(handle_path_command_with_auth).post(handle_path_command_with_auth),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .layer(middleware::from_fn_with_state(state.clone(), maintenance))
+        .layer(middleware::from_fn(encode_errors))
+        .route_layer(middleware::from_fn(track_requests))
+        .with_state(state)
 }
@@ -0,0 +1,89 @@
+use crate::console::Console;
+use crate::utils::AppError;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+
+/// How often certificate files are checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Time given to in-flight requests when shutting down
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+pub async fn load_tls_config(cert_path: &str, key_path: &str) -> Result<RustlsConfig, AppError> {
+    // Several crates link rustls, pick the provider explicitly
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    RustlsConfig::from_pem_file(cert_path, key_path)
+        .await
+        .map_err(|e| AppError::ServerError(format!("Failed to load TLS certificate: {}", e)))
+}
+
+/// Watch the certificate and key files and reload them when they change
+pub fn spawn_certificate_reloader(
+    tls_config: RustlsConfig,
+    cert_path: String,
+    key_path: String,
+    console: Console,
+) {
+    tokio::spawn(async move {
+        let mut last_modified = modified_at(&cert_path, &key_path);
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            let modified = modified_at(&cert_path, &key_path);
+            if modified == last_modified {
+                continue;
+            }
+
+            // A failed reload keeps serving the previous certificate and is
+            // retried on the next change of either file
+            last_modified = modified;
+            match tls_config.reload_from_pem_file(&cert_path, &key_path).await {
+                Ok(()) => {
+                    let _ = console.log_info("TLS certificate reloaded".to_string());
+                }
+                Err(e) => {
+                    let _ = console.log_error(format!("Failed to reload TLS certificate: {}", e));
+                }
+            }
+        }
+    });
+}
+
+/// Serve the router over HTTPS on an already bound listener
+pub async fn serve_tls(
+    listener: TcpListener,
+    app: Router,
+    tls_config: RustlsConfig,
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), AppError> {
+    let listener = listener
+        .into_std()
+        .map_err(|e| AppError::ServerError(format!("Failed to prepare TLS listener: {}", e)))?;
+
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown_signal.await;
+        shutdown_handle.graceful_shutdown(Some(SHUTDOWN_GRACE_PERIOD));
+    });
+
+    axum_server::from_tcp_rustls(listener, tls_config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .map_err(|e| AppError::ServerError(format!("Server error: {}", e)))
+}
+
+fn modified_at(cert_path: &str, key_path: &str) -> (Option<SystemTime>, Option<SystemTime>) {
+    let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    (modified(cert_path), modified(key_path))
+}
@@ -10,12 +10,16 @@ use clap::Parser;
 use client::RedisPool;
 use command::cli::Cli;
 use config::Config;
+use http::admin::{create_admin_router, spawn_admin_server, AdminState, Maintenance};
+use http::rate_limit::RateLimiter;
 use http::routes::create_router;
 use http::server::bind_server;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing_subscriber::{
     fmt::format::{DefaultFields, Format},
     layer::SubscriberExt,
+    reload,
     util::SubscriberInitExt,
     EnvFilter,
 };
@@ -54,8 +58,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_file(false)
         .with_timer(tracing_subscriber::fmt::time::SystemTime);
 
+    // The filter can be replaced at runtime from the admin API
+    let (log_filter_layer, log_filter) = reload::Layer::new(
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    );
+
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(log_filter_layer)
         .with(
             tracing_subscriber::fmt::layer()
                 .event_format(format)
@@ -76,12 +85,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Console::new now returns both the console and an optional shutdown receiver
     let (console, shutdown_receiver) = console::Console::new(
         config.server_address(),
-        format!("{}:{}", config.redis_host, config.redis_port),
+        config.redis_address(),
         config.max_connections,
         config.max_retry,
         VERSION.to_string(),
         config.console_mode.clone(),
         config.is_secure(),
+        config.is_tls(),
     )
     .map_err(|e| format!("Failed to initialize console: {}", e))?;
 
@@ -106,11 +116,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // Load the listener certificate before accepting connections
+    let tls_config = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => match http::tls::load_tls_config(cert, key).await {
+            Ok(tls_config) => {
+                http::tls::spawn_certificate_reloader(
+                    tls_config.clone(),
+                    cert.clone(),
+                    key.clone(),
+                    console.clone(),
+                );
+                console.log_info("TLS enabled on the listener".to_string())?;
+                Some(tls_config)
+            }
+            Err(e) => {
+                console.update_server_status(console::Status::BindError)?;
+                console.log_error(e.to_string())?;
+                let _ = console.cleanup();
+                std::process::exit(1);
+            }
+        },
+        _ => None,
+    };
+
     // Initialize Redis connection pool (with error handling)
-    console.log_info(format!(
-        "Connecting to Redis at {}:{}",
-        config.redis_host, config.redis_port
-    ))?;
+    console.log_info(format!("Connecting to Redis at {}", config.redis_address()))?;
 
     // Console is now Clone-safe (uses channel internally)
     let console_arc = Arc::new(console.clone());
@@ -120,6 +150,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let _ = console.log_info("Redis connection established".to_string());
             pool
         }
+        Err(e) if RedisPool::is_auth_error(&e) => {
+            let _ = console.update_redis_status(console::Status::AuthError);
+            let error_msg = format!(
+                "Redis rejected authentication: {}. Check SLASHLESS_REDIS_USERNAME and SLASHLESS_REDIS_PASSWORD",
+                e
+            );
+            let _ = console.log_error(error_msg.clone());
+            let _ = console.cleanup();
+            if matches!(console.mode(), config::ConsoleMode::Standard) {
+                // The TUI does not display logs, keep the reason visible after cleanup
+                eprintln!("{}", error_msg);
+            }
+            std::process::exit(1);
+        }
         Err(e) => {
             let _ = console.update_redis_status(console::Status::ConnectionError);
             let error_msg = format!("Failed to connect to Redis: {}", e);
@@ -130,9 +174,58 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // Create router
-    let router = create_router(pool.clone(), config.clone());
+    let rate_limiter = RateLimiter::new(config.rate_limit);
+    let maintenance = Maintenance::default();
+    let router = create_router(
+        pool.clone(),
+        config.clone(),
+        rate_limiter.clone(),
+        maintenance.clone(),
+    );
     let _ = console.log_info("Router created".to_string());
 
+    // Admin API, on its own port or under /admin
+    let router = match &config.admin {
+        Some(_) => {
+            let admin_router = create_admin_router(AdminState {
+                pool: pool.clone(),
+                config: config.clone(),
+                console: console.clone(),
+                maintenance,
+                log_filter,
+            });
+            match config.admin_address() {
+                Some(admin_address) => {
+                    let admin_listener = match tokio::net::TcpListener::bind(&admin_address).await {
+                        Ok(admin_listener) => admin_listener,
+                        Err(e) => {
+                            let _ = console.update_server_status(console::Status::BindError);
+                            let _ = console.log_error(format!(
+                                "Failed to bind the admin API to {}: {}",
+                                admin_address, e
+                            ));
+                            let _ = console.cleanup();
+                            std::process::exit(1);
+                        }
+                    };
+                    spawn_admin_server(
+                        admin_listener,
+                        admin_router,
+                        tls_config.clone(),
+                        console.clone(),
+                    );
+                    let _ = console.log_info(format!("Admin API listening on {}", admin_address));
+                    router
+                }
+                None => {
+                    let _ = console.log_info("Admin API served under /admin".to_string());
+                    router.nest("/admin", admin_router)
+                }
+            }
+        }
+        None => router,
+    };
+
     // Start Redis healthcheck task - ping every 2 seconds
     let pool_for_healthcheck = pool.clone();
     let console_for_healthcheck = console.clone();
@@ -143,6 +236,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         loop {
             interval.tick().await;
 
+            let _ = console_for_healthcheck.update_pool_stats(pool_for_healthcheck.stats());
+            let _ = console_for_healthcheck.update_rate_limited(rate_limiter.limited());
+
             // Ping Redis with retry (max 3 attempts)
             match pool_for_healthcheck.ping_with_retry().await {
                 Ok(pong) => {
@@ -213,13 +309,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         };
 
     // Start server with graceful shutdown
-    let server_result = axum::serve(listener, app)
+    let server_result = match tls_config {
+        Some(tls_config) => http::tls::serve_tls(listener, app, tls_config, shutdown_signal)
+            .await
+            .inspect_err(|e| {
+                let _ = console.log_error(e.to_string());
+            }),
+        None => axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
         .with_graceful_shutdown(shutdown_signal)
         .await
         .map_err(|e| {
             let _ = console.log_error(format!("Server error: {}", e));
             AppError::ServerError(format!("Server error: {}", e))
-        });
+        }),
+    };
 
     // Always cleanup console to restore terminal state
     if matches!(config.console_mode, config::ConsoleMode::Standard) {
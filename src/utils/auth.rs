@@ -1,6 +1,10 @@
+use super::jwt::is_jwt;
+use super::policy::CommandPolicy;
 use super::AppError;
-use crate::config::Config;
+use crate::config::{ApiToken, Config};
 use axum::extract::Request;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
 
 pub fn extract_bearer_token(request: &Request) -> Result<Option<String>, AppError> {
     let auth_header = request.headers().get("authorization");
@@ -23,20 +27,112 @@ pub fn extract_bearer_token(request: &Request) -> Result<Option<String>, AppErro
     }
 }
 
-pub fn validate_token(token: Option<&str>, config: &Config) -> Result<(), AppError> {
+/// Who made a request, and what it may run
+#[derive(Clone)]
+pub enum Caller {
+    /// Authentication is disabled
+    Anonymous(Arc<CommandPolicy>),
+    Token(Arc<ApiToken>),
+    /// JWT bearer token, with the policy built from its claims
+    Jwt {
+        name: String,
+        /// Rate limit bucket: the `sub` claim, or a hash of the token when it
+        /// has none
+        rate_key: String,
+        policy: Arc<CommandPolicy>,
+    },
+}
+
+impl Caller {
+    /// Name shown in the logs, never the secret itself
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Caller::Anonymous(_) => None,
+            Caller::Token(api_token) => Some(&api_token.name),
+            Caller::Jwt { name, .. } => Some(name),
+        }
+    }
+
+    pub fn policy(&self) -> &CommandPolicy {
+        match self {
+            Caller::Anonymous(policy) => policy,
+            Caller::Token(api_token) => &api_token.policy,
+            Caller::Jwt { policy, .. } => policy,
+        }
+    }
+
+    pub fn key_prefix(&self) -> Option<&str> {
+        match self {
+            Caller::Token(api_token) => api_token.prefix.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Key of the per-token rate limit bucket
+    pub fn rate_key(&self) -> Option<&str> {
+        match self {
+            Caller::Anonymous(_) => None,
+            Caller::Token(api_token) => Some(&api_token.name),
+            Caller::Jwt { rate_key, .. } => Some(rate_key),
+        }
+    }
+}
+
+/// Check the bearer token against the static tokens, then as a JWT
+pub fn validate_token(token: Option<&str>, config: &Config) -> Result<Caller, AppError> {
     // If no token is configured, skip authentication
-    if config.token.is_empty() {
-        return Ok(());
+    if !config.is_secure() {
+        return Ok(Caller::Anonymous(config.command_policy.clone()));
     }
 
     // If token is configured but not provided, deny access
     let provided_token = token.ok_or(AppError::Unauthorized)?;
 
-    if provided_token == config.token {
-        Ok(())
-    } else {
-        Err(AppError::Unauthorized)
+    let caller = match config
+        .tokens
+        .iter()
+        .find(|api_token| api_token.token == provided_token)
+    {
+        Some(api_token) if api_token.is_expired() => {
+            tracing::warn!("Refused expired token {}", api_token.name);
+            return Err(AppError::Unauthorized);
+        }
+        Some(api_token) if api_token.is_revoked() => {
+            tracing::warn!("Refused revoked token {}", api_token.name);
+            return Err(AppError::Unauthorized);
+        }
+        Some(api_token) => Caller::Token(api_token.clone()),
+        None => match &config.jwt {
+            Some(jwt) if is_jwt(provided_token) => {
+                let claims = jwt.verify(provided_token)?;
+                let name = format!("jwt:{}", claims.sub.as_deref().unwrap_or("-"));
+                let policy = claims.policy(&config.command_policy).map_err(|e| {
+                    tracing::warn!("Refused {}: {}", name, e);
+                    AppError::Unauthorized
+                })?;
+                // Tokens without a subject must not share one bucket
+                let rate_key = match &claims.sub {
+                    Some(_) => name.clone(),
+                    None => {
+                        let mut hasher = DefaultHasher::new();
+                        provided_token.hash(&mut hasher);
+                        format!("jwt:#{:016x}", hasher.finish())
+                    }
+                };
+                Caller::Jwt {
+                    name,
+                    rate_key,
+                    policy: Arc::new(policy),
+                }
+            }
+            _ => return Err(AppError::Unauthorized),
+        },
+    };
+
+    if let Some(name) = caller.name() {
+        tracing::debug!("Request authenticated with token {}", name);
     }
+    Ok(caller)
 }
 
 pub fn check_encoding_header(request: &Request) -> bool {
@@ -47,3 +143,30 @@ pub fn check_encoding_header(request: &Request) -> bool {
     }
     false
 }
+
+/// Whether request arguments are base64 encoded (binary-safe input)
+pub fn check_request_encoding_header(request: &Request) -> bool {
+    request
+        .headers()
+        .get("slashless-request-encoding")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("base64"))
+}
+
+/// Whether the client asked to read from the primary (read-your-writes)
+pub fn check_read_primary_header(request: &Request) -> bool {
+    request
+        .headers()
+        .get("slashless-read-primary")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| matches!(value.to_lowercase().as_str(), "true" | "1"))
+}
+
+/// Whether the client asked for RESP3 replies
+pub fn check_protocol_header(request: &Request) -> bool {
+    request
+        .headers()
+        .get("slashless-protocol")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("resp3"))
+}
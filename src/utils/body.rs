@@ -0,0 +1,267 @@
+use super::AppError;
+use axum::http::{header, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use base64::{engine::general_purpose, Engine as _};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::Number;
+use std::fmt;
+
+/// Encoding of a request or response body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+    #[default]
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl BodyFormat {
+    /// Format named by a media type, `None` if not supported
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" => Some(Self::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Self::MsgPack)
+            }
+            "application/cbor" => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MsgPack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// Whether the format has byte strings, so bulk replies can be sent as is
+    pub fn has_bytes(self) -> bool {
+        !matches!(self, Self::Json)
+    }
+}
+
+/// Body of a request or response: JSON values plus byte strings, so the
+/// same handlers serve JSON, MessagePack and CBOR.
+///
+/// In JSON, byte strings are written as `{"base64": "..."}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyValue {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Vec<BodyValue>),
+    Object(Vec<(String, BodyValue)>),
+}
+
+impl BodyValue {
+    /// Parse a request body
+    pub fn decode(bytes: &[u8], format: BodyFormat) -> Result<Self, AppError> {
+        let invalid = |name: &str| AppError::MalformedRequest(format!("Invalid {} body", name));
+        match format {
+            BodyFormat::Json => serde_json::from_slice(bytes).map_err(|_| invalid("JSON")),
+            BodyFormat::MsgPack => rmp_serde::from_slice(bytes).map_err(|_| invalid("MessagePack")),
+            BodyFormat::Cbor => ciborium::from_reader(bytes).map_err(|_| invalid("CBOR")),
+        }
+    }
+
+    /// Response with the body encoded in `format`
+    pub fn render(self, format: BodyFormat) -> Response {
+        let encoded = match format {
+            BodyFormat::Json => serde_json::to_vec(&self).map_err(|e| e.to_string()),
+            BodyFormat::MsgPack => rmp_serde::to_vec(&self).map_err(|e| e.to_string()),
+            BodyFormat::Cbor => {
+                let mut encoded = Vec::new();
+                ciborium::into_writer(&self, &mut encoded)
+                    .map(|_| encoded)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        match encoded {
+            Ok(encoded) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(format.content_type()),
+                )],
+                encoded,
+            )
+                .into_response(),
+            Err(e) => {
+                AppError::ServerError(format!("Failed to encode response: {}", e)).into_response()
+            }
+        }
+    }
+
+    /// Object with the given fields, in order
+    pub fn object<const N: usize>(fields: [(&str, BodyValue); N]) -> Self {
+        Self::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Self::Array(_))
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<BodyValue>> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Field of an object
+    pub fn get(&self, key: &str) -> Option<&BodyValue> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for BodyValue {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<&str> for BodyValue {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<i64> for BodyValue {
+    fn from(i: i64) -> Self {
+        Self::Number(i.into())
+    }
+}
+
+impl From<Vec<BodyValue>> for BodyValue {
+    fn from(values: Vec<BodyValue>) -> Self {
+        Self::Array(values)
+    }
+}
+
+impl fmt::Display for BodyValue {
+    /// JSON text of the value
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+impl Serialize for BodyValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Number(n) => n.serialize(serializer),
+            Self::String(s) => serializer.serialize_str(s),
+            // Text formats have no byte strings
+            Self::Bytes(bytes) if serializer.is_human_readable() => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("base64", &general_purpose::STANDARD.encode(bytes))?;
+                map.end()
+            }
+            Self::Bytes(bytes) => serializer.serialize_bytes(bytes),
+            Self::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Self::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BodyValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BodyValueVisitor)
+    }
+}
+
+struct BodyValueVisitor;
+
+impl<'de> Visitor<'de> for BodyValueVisitor {
+    type Value = BodyValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON, MessagePack or CBOR value")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<BodyValue, E> {
+        Ok(BodyValue::Bool(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, i: i64) -> Result<BodyValue, E> {
+        Ok(BodyValue::Number(i.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, u: u64) -> Result<BodyValue, E> {
+        Ok(BodyValue::Number(u.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<BodyValue, E> {
+        Ok(Number::from_f64(f).map_or(BodyValue::Null, BodyValue::Number))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<BodyValue, E> {
+        Ok(BodyValue::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<BodyValue, E> {
+        Ok(BodyValue::String(s))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<BodyValue, E> {
+        Ok(BodyValue::Bytes(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<BodyValue, E> {
+        Ok(BodyValue::Bytes(bytes))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<BodyValue, E> {
+        Ok(BodyValue::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<BodyValue, E> {
+        Ok(BodyValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<BodyValue, D::Error> {
+        BodyValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BodyValue, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(BodyValue::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<BodyValue, A::Error> {
+        let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, BodyValue>()? {
+            fields.push((key, value));
+        }
+        Ok(BodyValue::Object(fields))
+    }
+}
@@ -0,0 +1,390 @@
+/// Where the key arguments of a command are located.
+///
+/// Positions count the command name as argument 0, like in `COMMAND INFO`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySpec {
+    /// The command takes no key (PING, INFO, KEYS, ...)
+    None,
+    /// Keys from `first` to `last` every `step` arguments, a negative `last`
+    /// counts from the end (-1 is the last argument)
+    Range {
+        first: usize,
+        last: isize,
+        step: usize,
+    },
+    /// The number of keys is given at position `at` and the keys follow it
+    /// (EVAL, ZUNION, LMPOP, ...)
+    NumKeys { at: usize },
+    /// A destination key at position 1, then a key count at position 2 and the
+    /// keys following it (ZUNIONSTORE, ZINTERSTORE, ZDIFFSTORE)
+    DestinationNumKeys,
+    /// Keys follow the STREAMS keyword and fill half of the remaining
+    /// arguments, the other half being IDs (XREAD, XREADGROUP)
+    Streams,
+    /// A key at position 1, plus the destination key following a STORE or
+    /// STOREDIST option (GEORADIUS, GEORADIUSBYMEMBER, SORT)
+    Store,
+    /// Commands missing from the table (modules, newer Redis versions). Their
+    /// key is assumed to be at position 1 for routing, but tokens with a key
+    /// prefix or key patterns cannot run them.
+    Unknown,
+}
+
+const fn keys(first: usize, last: isize, step: usize) -> KeySpec {
+    KeySpec::Range { first, last, step }
+}
+
+/// Single key in first position, the shape of most commands
+const FIRST_KEY: KeySpec = keys(1, 1, 1);
+
+/// Key specification of a command, looked up by its (case insensitive) name.
+///
+/// Container subcommands (OBJECT, XINFO, ...) need the full argument list to
+/// be resolved, use `key_positions` for that.
+pub fn key_spec(name: &str) -> KeySpec {
+    match name.to_uppercase().as_str() {
+        // Server, connection and scripting management commands
+        "PING" | "ECHO" | "AUTH" | "HELLO" | "SELECT" | "QUIT" | "RESET" | "INFO" | "TIME"
+        | "DBSIZE" | "KEYS" | "SCAN" | "RANDOMKEY" | "FLUSHALL" | "FLUSHDB" | "SWAPDB"
+        | "CONFIG" | "CLIENT" | "COMMAND" | "DEBUG" | "SAVE" | "BGSAVE" | "BGREWRITEAOF"
+        | "LASTSAVE" | "SHUTDOWN" | "SLOWLOG" | "LATENCY" | "MONITOR" | "ROLE" | "ACL"
+        | "CLUSTER" | "READONLY" | "READWRITE" | "WAIT" | "WAITAOF" | "REPLICAOF" | "SLAVEOF"
+        | "SYNC" | "PSYNC" | "FAILOVER" | "LOLWUT" | "MODULE" | "SCRIPT" | "FUNCTION" | "MULTI"
+        | "EXEC" | "DISCARD" | "UNWATCH" | "PUBLISH" | "SPUBLISH" | "PUBSUB" | "SUBSCRIBE"
+        | "UNSUBSCRIBE" | "PSUBSCRIBE" | "PUNSUBSCRIBE" | "SSUBSCRIBE" | "SUNSUBSCRIBE" => {
+            KeySpec::None
+        }
+
+        // Strings, bitmaps and generic keys
+        "GET" | "SET" | "SETNX" | "SETEX" | "PSETEX" | "GETSET" | "GETDEL" | "GETEX" | "APPEND"
+        | "STRLEN" | "INCR" | "INCRBY" | "INCRBYFLOAT" | "DECR" | "DECRBY" | "GETRANGE"
+        | "SETRANGE" | "SUBSTR" | "GETBIT" | "SETBIT" | "BITCOUNT" | "BITPOS" | "BITFIELD"
+        | "BITFIELD_RO" | "EXPIRE" | "PEXPIRE" | "EXPIREAT" | "PEXPIREAT" | "EXPIRETIME"
+        | "PEXPIRETIME" | "TTL" | "PTTL" | "PERSIST" | "TYPE" | "DUMP" | "RESTORE" | "MOVE"
+        | "SORT_RO"
+        // Hashes
+        | "HSET" | "HSETNX" | "HMSET" | "HGET" | "HMGET" | "HGETALL" | "HDEL" | "HEXISTS"
+        | "HINCRBY" | "HINCRBYFLOAT" | "HKEYS" | "HVALS" | "HLEN" | "HSTRLEN" | "HRANDFIELD"
+        | "HSCAN" | "HEXPIRE" | "HPEXPIRE" | "HEXPIREAT" | "HPEXPIREAT" | "HTTL" | "HPTTL"
+        | "HEXPIRETIME" | "HPEXPIRETIME" | "HPERSIST" | "HGETDEL" | "HGETEX" | "HSETEX"
+        // Lists
+        | "LPUSH" | "RPUSH" | "LPUSHX" | "RPUSHX" | "LPOP" | "RPOP" | "LRANGE" | "LLEN"
+        | "LINDEX" | "LSET" | "LINSERT" | "LREM" | "LTRIM" | "LPOS"
+        // Sets
+        | "SADD" | "SREM" | "SMEMBERS" | "SISMEMBER" | "SMISMEMBER" | "SCARD" | "SPOP"
+        | "SRANDMEMBER" | "SSCAN"
+        // Sorted sets
+        | "ZADD" | "ZREM" | "ZINCRBY" | "ZSCORE" | "ZMSCORE" | "ZRANK" | "ZREVRANK" | "ZCARD"
+        | "ZCOUNT" | "ZLEXCOUNT" | "ZRANGE" | "ZRANGEBYSCORE" | "ZRANGEBYLEX" | "ZREVRANGE"
+        | "ZREVRANGEBYSCORE" | "ZREVRANGEBYLEX" | "ZREMRANGEBYRANK" | "ZREMRANGEBYSCORE"
+        | "ZREMRANGEBYLEX" | "ZPOPMIN" | "ZPOPMAX" | "ZRANDMEMBER" | "ZSCAN"
+        // Geo, HyperLogLog and streams
+        | "GEOADD" | "GEOPOS" | "GEODIST" | "GEOHASH" | "GEOSEARCH" | "GEORADIUS_RO"
+        | "GEORADIUSBYMEMBER_RO" | "PFADD" | "XADD" | "XRANGE" | "XREVRANGE" | "XLEN" | "XDEL"
+        | "XTRIM" | "XACK" | "XCLAIM" | "XAUTOCLAIM" | "XPENDING" | "XSETID"
+        // JSON module
+        | "JSON.SET" | "JSON.GET" | "JSON.DEL" | "JSON.FORGET" | "JSON.TYPE" | "JSON.STRLEN"
+        | "JSON.STRAPPEND" | "JSON.ARRAPPEND" | "JSON.ARRINDEX" | "JSON.ARRINSERT"
+        | "JSON.ARRLEN" | "JSON.ARRPOP" | "JSON.ARRTRIM" | "JSON.OBJKEYS" | "JSON.OBJLEN"
+        | "JSON.NUMINCRBY" | "JSON.NUMMULTBY" | "JSON.TOGGLE" | "JSON.CLEAR" | "JSON.RESP"
+        | "JSON.MERGE" => FIRST_KEY,
+
+        // Every argument is a key
+        "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "MGET" | "WATCH" | "SINTER" | "SUNION"
+        | "SDIFF" | "SINTERSTORE" | "SUNIONSTORE" | "SDIFFSTORE" | "PFCOUNT" | "PFMERGE" => {
+            keys(1, -1, 1)
+        }
+
+        // Source and destination keys, or the two compared keys
+        "RENAME" | "RENAMENX" | "COPY" | "SMOVE" | "RPOPLPUSH" | "LMOVE" | "BLMOVE"
+        | "BRPOPLPUSH" | "GEOSEARCHSTORE" | "ZRANGESTORE" | "LCS" => keys(1, 2, 1),
+
+        // Key/value pairs, and JSON.MSET key path value triples
+        "MSET" | "MSETNX" => keys(1, -1, 2),
+        "JSON.MSET" => keys(1, -1, 3),
+
+        // JSON.MGET key [key ...] path
+        "JSON.MGET" => keys(1, -2, 1),
+
+        // Blocking pops end with a timeout
+        "BLPOP" | "BRPOP" | "BZPOPMIN" | "BZPOPMAX" => keys(1, -2, 1),
+
+        // BITOP operation destkey key [key ...]
+        "BITOP" => keys(2, -1, 1),
+
+        "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => KeySpec::DestinationNumKeys,
+        "ZUNION" | "ZINTER" | "ZDIFF" | "ZINTERCARD" | "SINTERCARD" | "LMPOP" | "ZMPOP" => {
+            KeySpec::NumKeys { at: 1 }
+        }
+        "BLMPOP" | "BZMPOP" | "EVAL" | "EVALSHA" | "EVAL_RO" | "EVALSHA_RO" | "FCALL"
+        | "FCALL_RO" => KeySpec::NumKeys { at: 2 },
+
+        "XREAD" | "XREADGROUP" => KeySpec::Streams,
+        "GEORADIUS" | "GEORADIUSBYMEMBER" | "SORT" => KeySpec::Store,
+
+        // Container commands with the key after the subcommand
+        "OBJECT" | "XINFO" | "XGROUP" | "MEMORY" => keys(2, 2, 1),
+
+        _ => KeySpec::Unknown,
+    }
+}
+
+/// Whether the key arguments of a command can be located, so they can be
+/// prefixed or checked against key patterns
+pub fn has_known_keys(name: &str) -> bool {
+    key_spec(name) != KeySpec::Unknown
+}
+
+/// Indexes of the key arguments in a full command (name included)
+pub fn key_positions<A: AsRef<[u8]>>(args: &[A]) -> Vec<usize> {
+    let Some(name) = args.first() else {
+        return Vec::new();
+    };
+    let name = String::from_utf8_lossy(name.as_ref());
+    let len = args.len();
+
+    let spec = match key_spec(&name) {
+        KeySpec::Unknown => FIRST_KEY,
+        spec => spec,
+    };
+    match spec {
+        KeySpec::None | KeySpec::Unknown => Vec::new(),
+        KeySpec::Range { first, last, step } => {
+            // MEMORY only has a key for MEMORY USAGE
+            if name.eq_ignore_ascii_case("MEMORY")
+                && !args
+                    .get(1)
+                    .is_some_and(|sub| sub.as_ref().eq_ignore_ascii_case(b"USAGE"))
+            {
+                return Vec::new();
+            }
+
+            let last = if last < 0 { len as isize + last } else { last };
+            if last < first as isize {
+                return Vec::new();
+            }
+            (first..=(last as usize).min(len.saturating_sub(1)))
+                .step_by(step)
+                .collect()
+        }
+        KeySpec::NumKeys { at } => numkeys_positions(args, at),
+        KeySpec::DestinationNumKeys => {
+            let mut positions = vec![1];
+            positions.extend(numkeys_positions(args, 2));
+            positions.retain(|&position| position < len);
+            positions
+        }
+        KeySpec::Streams => {
+            let Some(streams) = args
+                .iter()
+                .position(|arg| arg.as_ref().eq_ignore_ascii_case(b"STREAMS"))
+            else {
+                return Vec::new();
+            };
+            let count = (len - streams - 1) / 2;
+            (streams + 1..streams + 1 + count).collect()
+        }
+        KeySpec::Store => {
+            let mut positions = vec![1];
+            positions.extend(
+                args.iter()
+                    .enumerate()
+                    .skip(2)
+                    .filter(|(_, arg)| {
+                        let arg = arg.as_ref();
+                        arg.eq_ignore_ascii_case(b"STORE") || arg.eq_ignore_ascii_case(b"STOREDIST")
+                    })
+                    .map(|(position, _)| position + 1),
+            );
+            positions.retain(|&position| position < len);
+            positions
+        }
+    }
+}
+
+/// Key arguments of a full command (name included)
+pub fn command_keys<A: AsRef<[u8]>>(args: &[A]) -> Vec<&[u8]> {
+    key_positions(args)
+        .into_iter()
+        .map(|position| args[position].as_ref())
+        .collect()
+}
+
+/// Indexes of the Pub/Sub channel (or pattern) arguments in a full command
+/// (name included)
+pub fn channel_positions<A: AsRef<[u8]>>(args: &[A]) -> Vec<usize> {
+    let Some(name) = args.first() else {
+        return Vec::new();
+    };
+    match name.as_ref().to_ascii_uppercase().as_slice() {
+        b"PUBLISH" | b"SPUBLISH" => (1..args.len().min(2)).collect(),
+        b"SUBSCRIBE" | b"SSUBSCRIBE" | b"PSUBSCRIBE" | b"UNSUBSCRIBE" | b"SUNSUBSCRIBE"
+        | b"PUNSUBSCRIBE" => (1..args.len()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a command changes the state of the connection it runs on: selected
+/// database, authenticated user, protocol, an open transaction, Pub/Sub mode or
+/// client flags.
+///
+/// Pooled connections serve one request after another, so these commands
+/// would leak into later requests, possibly of other tokens.
+pub fn changes_connection_state<A: AsRef<[u8]>>(args: &[A]) -> bool {
+    let Some(name) = args.first() else {
+        return false;
+    };
+    match name.as_ref().to_ascii_uppercase().as_slice() {
+        b"SELECT" | b"MULTI" | b"WATCH" | b"AUTH" | b"HELLO" | b"RESET" | b"QUIT"
+        | b"SUBSCRIBE" | b"PSUBSCRIBE" | b"SSUBSCRIBE" | b"READONLY" | b"READWRITE" => true,
+        b"CLIENT" => args.get(1).is_some_and(|subcommand| {
+            matches!(
+                subcommand.as_ref().to_ascii_uppercase().as_slice(),
+                b"REPLY" | b"TRACKING" | b"CACHING" | b"SETNAME" | b"NO-EVICT" | b"NO-TOUCH"
+            )
+        }),
+        _ => false,
+    }
+}
+
+fn numkeys_positions<A: AsRef<[u8]>>(args: &[A], at: usize) -> Vec<usize> {
+    let count = args
+        .get(at)
+        .and_then(|count| std::str::from_utf8(count.as_ref()).ok())
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(0);
+    (at + 1..(at + 1 + count).min(args.len())).collect()
+}
+
+/// Whether a command only reads data, so it can be served by a replica and run
+/// with a read-only token.
+///
+/// Unknown commands are treated as writes.
+pub fn is_read_only(name: &str) -> bool {
+    matches!(
+        name.to_uppercase().as_str(),
+        // Connection
+        "PING" | "ECHO" | "TIME"
+            // Strings and generic keys
+            | "GET" | "MGET" | "GETRANGE" | "SUBSTR" | "STRLEN" | "LCS" | "EXISTS" | "TYPE" | "TTL"
+            | "PTTL" | "EXPIRETIME" | "PEXPIRETIME" | "DUMP" | "OBJECT" | "TOUCH"
+            | "GETBIT" | "BITCOUNT" | "BITPOS" | "BITFIELD_RO" | "SORT_RO" | "DBSIZE"
+            | "RANDOMKEY" | "KEYS" | "SCAN"
+            // Hashes
+            | "HGET" | "HMGET" | "HGETALL" | "HKEYS" | "HVALS" | "HLEN" | "HEXISTS"
+            | "HSTRLEN" | "HRANDFIELD" | "HSCAN" | "HTTL" | "HPTTL" | "HEXPIRETIME"
+            | "HPEXPIRETIME"
+            // Lists
+            | "LRANGE" | "LLEN" | "LINDEX" | "LPOS"
+            // Sets
+            | "SMEMBERS" | "SISMEMBER" | "SMISMEMBER" | "SCARD" | "SRANDMEMBER" | "SINTER"
+            | "SINTERCARD" | "SUNION" | "SDIFF" | "SSCAN"
+            // Sorted sets
+            | "ZRANGE" | "ZRANGEBYSCORE" | "ZRANGEBYLEX" | "ZREVRANGE" | "ZREVRANGEBYSCORE"
+            | "ZREVRANGEBYLEX" | "ZSCORE" | "ZMSCORE" | "ZRANK" | "ZREVRANK" | "ZCARD"
+            | "ZCOUNT" | "ZLEXCOUNT" | "ZRANDMEMBER" | "ZSCAN" | "ZUNION" | "ZINTER"
+            | "ZINTERCARD" | "ZDIFF"
+            // Geo, HyperLogLog and streams
+            | "GEOPOS" | "GEODIST" | "GEOHASH" | "GEOSEARCH" | "GEORADIUS_RO"
+            | "GEORADIUSBYMEMBER_RO" | "PFCOUNT" | "XRANGE" | "XREVRANGE" | "XLEN" | "XREAD"
+            | "XINFO" | "XPENDING"
+            // Receiving Pub/Sub messages
+            | "SUBSCRIBE" | "PSUBSCRIBE" | "SSUBSCRIBE"
+            // Read-only scripts and functions
+            | "EVAL_RO" | "EVALSHA_RO" | "FCALL_RO"
+            // JSON module
+            | "JSON.GET" | "JSON.MGET" | "JSON.TYPE" | "JSON.STRLEN" | "JSON.ARRLEN"
+            | "JSON.ARRINDEX" | "JSON.OBJKEYS" | "JSON.OBJLEN"
+    )
+}
+
+/// Categories usable in command allow and deny lists, named after the Redis
+/// ACL categories
+pub const CATEGORIES: &[&str] = &[
+    "all",
+    "read",
+    "write",
+    "admin",
+    "dangerous",
+    "scripting",
+    "pubsub",
+];
+
+/// Whether a command belongs to a category of `CATEGORIES` (lowercase)
+pub fn in_category(name: &str, category: &str) -> bool {
+    let name = name.to_uppercase();
+    match category {
+        "all" => true,
+        "read" => is_read_only(&name),
+        "write" => !is_read_only(&name),
+        "admin" => is_admin(&name),
+        // Administration, plus commands wiping or scanning the whole keyspace
+        "dangerous" => {
+            is_admin(&name)
+                || matches!(
+                    name.as_str(),
+                    "FLUSHALL"
+                        | "FLUSHDB"
+                        | "KEYS"
+                        | "SWAPDB"
+                        | "MIGRATE"
+                        | "RESTORE"
+                        | "SORT"
+                        | "INFO"
+                        | "ROLE"
+                )
+        }
+        "scripting" => matches!(
+            name.as_str(),
+            "EVAL"
+                | "EVALSHA"
+                | "EVAL_RO"
+                | "EVALSHA_RO"
+                | "SCRIPT"
+                | "FCALL"
+                | "FCALL_RO"
+                | "FUNCTION"
+        ),
+        "pubsub" => matches!(
+            name.as_str(),
+            "PUBLISH"
+                | "SPUBLISH"
+                | "PUBSUB"
+                | "SUBSCRIBE"
+                | "UNSUBSCRIBE"
+                | "PSUBSCRIBE"
+                | "PUNSUBSCRIBE"
+                | "SSUBSCRIBE"
+                | "SUNSUBSCRIBE"
+        ),
+        _ => false,
+    }
+}
+
+/// Server administration commands (uppercase name)
+fn is_admin(name: &str) -> bool {
+    matches!(
+        name,
+        "CONFIG"
+            | "DEBUG"
+            | "SHUTDOWN"
+            | "SAVE"
+            | "BGSAVE"
+            | "BGREWRITEAOF"
+            | "LASTSAVE"
+            | "REPLICAOF"
+            | "SLAVEOF"
+            | "FAILOVER"
+            | "SYNC"
+            | "PSYNC"
+            | "MODULE"
+            | "MONITOR"
+            | "ACL"
+            | "CLIENT"
+            | "CLUSTER"
+            | "SLOWLOG"
+            | "LATENCY"
+            | "PFDEBUG"
+            | "PFSELFTEST"
+    )
+}
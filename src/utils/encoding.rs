@@ -1,42 +1,85 @@
-use serde_json::Value;
+use super::body::BodyValue;
+use super::options::RequestOptions;
+use super::AppError;
+use base64::{engine::general_purpose, Engine as _};
 
-pub fn encode_result(result: &Value, encoding_enabled: bool) -> Value {
-    if !encoding_enabled {
-        return result.clone();
+/// Body form of a bulk string reply.
+///
+/// MessagePack and CBOR responses carry the raw bytes as a byte string. In
+/// JSON, with `upstash-encoding: base64`, the raw bytes are base64 encoded.
+/// Otherwise UTF-8 values are returned as plain strings and binary ones as
+/// `{"base64": "..."}`. RESP3 map keys are escaped so that a map never looks
+/// like such a value, see `map_key`.
+pub fn bulk_string_to_body(bytes: Vec<u8>, options: RequestOptions) -> BodyValue {
+    if options.format.has_bytes() {
+        return BodyValue::Bytes(bytes);
     }
-
-    // Handle result wrapper format
-    if let Some(obj) = result.as_object() {
-        if let Some(result_value) = obj.get("result") {
-            let mut encoded_obj = serde_json::Map::new();
-            encoded_obj.insert("result".to_string(), encode_result_value(result_value));
-            return Value::Object(encoded_obj);
-        }
+    if options.encoding_enabled {
+        return BodyValue::String(general_purpose::STANDARD.encode(bytes));
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => BodyValue::String(s),
+        Err(e) => BodyValue::Bytes(e.into_bytes()),
     }
+}
 
-    encode_result_value(result)
+/// Body form of a text reply (status, verbatim string, ...), base64 encoded
+/// like bulk strings when encoding is enabled
+pub fn text_to_body(text: String, options: RequestOptions) -> BodyValue {
+    if options.encoding_enabled && !options.format.has_bytes() {
+        BodyValue::String(general_purpose::STANDARD.encode(text))
+    } else {
+        BodyValue::String(text)
+    }
 }
 
-fn encode_result_value(value: &Value) -> Value {
-    match value {
-        Value::Null => Value::Null,
-        Value::Bool(b) => Value::Bool(*b),
-        Value::Number(n) => Value::Number(n.clone()),
-        Value::String(s) => {
-            // Encode strings to base64
-            use base64::{engine::general_purpose, Engine as _};
-            Value::String(general_purpose::STANDARD.encode(s.as_bytes()))
-        }
-        Value::Array(arr) => {
-            // Recursively encode array elements
-            Value::Array(arr.iter().map(encode_result_value).collect())
-        }
-        Value::Object(obj) => {
-            let mut encoded = serde_json::Map::new();
-            for (k, v) in obj {
-                encoded.insert(k.clone(), encode_result_value(v));
+/// Arguments of a command array, as the bytes sent to Redis.
+///
+/// With `base64_args`, string arguments (command name included) are
+/// base64-decoded so clients can send arbitrary bytes. Byte strings
+/// (MessagePack, CBOR), numbers, booleans and null are taken literally in
+/// both modes.
+pub fn command_args(
+    command_array: &[BodyValue],
+    base64_args: bool,
+) -> Result<Vec<Vec<u8>>, AppError> {
+    command_array
+        .iter()
+        .enumerate()
+        .map(|(position, v)| match v {
+            BodyValue::String(s) if base64_args => {
+                general_purpose::STANDARD.decode(s).map_err(|_| {
+                    AppError::MalformedRequest(format!(
+                        "Invalid base64 argument at position {}",
+                        position
+                    ))
+                })
             }
-            Value::Object(encoded)
-        }
+            BodyValue::String(s) => Ok(s.clone().into_bytes()),
+            BodyValue::Bytes(bytes) => Ok(bytes.clone()),
+            BodyValue::Number(n) => Ok(n.to_string().into_bytes()),
+            BodyValue::Bool(b) => Ok(b.to_string().into_bytes()),
+            BodyValue::Null => Ok(Vec::new()),
+            _ => Ok(v.to_string().into_bytes()),
+        })
+        .collect()
+}
+
+/// Build a Redis command from its arguments, `None` when there is none
+pub fn build_command(args: &[Vec<u8>]) -> Option<redis::Cmd> {
+    let (name, args) = args.split_first()?;
+    let mut cmd = redis::Cmd::new();
+    cmd.arg(name.as_slice());
+    for arg in args {
+        cmd.arg(arg.as_slice());
+    }
+    Some(cmd)
+}
+
+/// Name of a command, as given by the client
+pub fn command_name(cmd: &redis::Cmd) -> &[u8] {
+    match cmd.args_iter().next() {
+        Some(redis::Arg::Simple(name)) => name,
+        _ => b"",
     }
 }
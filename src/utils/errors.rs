@@ -1,7 +1,7 @@
+use super::body::BodyValue;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::json;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,10 +12,34 @@ pub enum AppError {
     #[error("Invalid token")]
     Unauthorized,
 
+    /// Command not allowed for the token (read-only token running a write)
+    #[error("{0}")]
+    Forbidden(String),
+
+    /// A rate limit is exhausted, see the rate limiting middleware
+    #[error("{0}")]
+    RateLimited(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    /// Maintenance mode, toggled from the admin API
+    #[error("{0}")]
+    Unavailable(String),
+
     #[error("Malformed request: {0}")]
     MalformedRequest(String),
 
-    #[allow(dead_code)]
+    #[error("Transaction aborted: {0}")]
+    TransactionAborted(String),
+
+    /// A watched key changed before EXEC, the client may retry
+    #[error("Transaction aborted by WATCH: {message}")]
+    WatchAborted {
+        message: String,
+        reads: Vec<BodyValue>,
+    },
+
     #[error("Connection error: {0}")]
     ConnectionError(String),
 
@@ -23,11 +47,29 @@ pub enum AppError {
     ServerError(String),
 }
 
+/// Body of an error response, kept in the response extensions so it can be
+/// encoded again in the format negotiated with the client
+#[derive(Debug, Clone)]
+pub struct ErrorBody(pub BodyValue);
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Invalid token".to_string()),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::RateLimited(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::Unavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
             AppError::MalformedRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::TransactionAborted(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::WatchAborted { message, reads } => {
+                let body = BodyValue::object([
+                    ("error", message.into()),
+                    ("aborted", BodyValue::Bool(true)),
+                    ("reads", BodyValue::Array(reads)),
+                ]);
+                return error_response(StatusCode::CONFLICT, body);
+            }
             AppError::ConnectionError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::ServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::Redis(err) => {
@@ -37,7 +79,13 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = Json(json!({ "error": error_message }));
-        (status, body).into_response()
+        error_response(status, BodyValue::object([("error", error_message.into())]))
     }
 }
+
+/// JSON error response, see `encode_errors` for the other formats
+fn error_response(status: StatusCode, body: BodyValue) -> Response {
+    let mut response = (status, Json(&body)).into_response();
+    response.extensions_mut().insert(ErrorBody(body));
+    response
+}
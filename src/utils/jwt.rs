@@ -0,0 +1,157 @@
+use super::policy::CommandPolicy;
+use super::AppError;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+
+/// Keys and expected claims of the JWTs accepted as bearer tokens
+#[derive(Clone)]
+pub struct JwtVerifier {
+    /// HS256 shared secret
+    secret: Option<DecodingKey>,
+    /// RS256 and EdDSA public keys from the JWKS file, with their key id
+    public_keys: Vec<(Option<String>, Algorithm, DecodingKey)>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+/// Claims restricting what a JWT may run, on top of `exp`
+#[derive(Debug, Deserialize)]
+pub struct JwtClaims {
+    /// Shown in the logs, like the name of a static token
+    #[serde(default)]
+    pub sub: Option<String>,
+    /// Commands and `@categories` allowed, SLASHLESS_ALLOW_COMMANDS if absent
+    #[serde(default)]
+    pub commands: Option<Vec<String>>,
+    /// Glob patterns every key has to match, any key if absent
+    #[serde(default)]
+    pub keys: Option<Vec<String>>,
+}
+
+impl JwtClaims {
+    /// Command policy of the token, refining the default one: the `commands`
+    /// claim can only narrow the server allowlist
+    pub fn policy(&self, default: &CommandPolicy) -> Result<CommandPolicy, String> {
+        let policy = match &self.commands {
+            Some(commands) => default.restrict_allow(commands)?,
+            None => default.clone(),
+        };
+        Ok(match &self.keys {
+            Some(patterns) => policy.with_key_patterns(patterns),
+            None => policy,
+        })
+    }
+}
+
+impl JwtVerifier {
+    /// Verifier for an HS256 secret and/or a JWKS file, `None` if neither is set
+    pub fn load(
+        secret: Option<&str>,
+        jwks_file: Option<&str>,
+        issuer: Option<String>,
+        audience: Option<String>,
+    ) -> Result<Option<Self>, String> {
+        if secret.is_none() && jwks_file.is_none() {
+            return Ok(None);
+        }
+
+        let public_keys = match jwks_file {
+            Some(path) => {
+                let jwks = fs::read_to_string(path).map_err(|e| {
+                    format!(
+                        "SLASHLESS_JWT_JWKS_FILE could not be read ({}): {}",
+                        path, e
+                    )
+                })?;
+                let jwks: JwkSet = serde_json::from_str(&jwks)
+                    .map_err(|e| format!("SLASHLESS_JWT_JWKS_FILE is not a valid JWKS: {}", e))?;
+                jwks.keys
+                    .iter()
+                    .map(|jwk| {
+                        let algorithm =
+                            match &jwk.algorithm {
+                                AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                                AlgorithmParameters::OctetKeyPair(params)
+                                    if params.curve == EllipticCurve::Ed25519 =>
+                                {
+                                    Algorithm::EdDSA
+                                }
+                                _ => return Err(
+                                    "SLASHLESS_JWT_JWKS_FILE may only hold RSA and Ed25519 keys"
+                                        .to_string(),
+                                ),
+                            };
+                        let key = DecodingKey::from_jwk(jwk).map_err(|e| {
+                            format!("SLASHLESS_JWT_JWKS_FILE holds an invalid key: {}", e)
+                        })?;
+                        Ok((jwk.common.key_id.clone(), algorithm, key))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?
+            }
+            None => Vec::new(),
+        };
+
+        Ok(Some(Self {
+            secret: secret.map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+            public_keys,
+            issuer,
+            audience,
+        }))
+    }
+
+    /// Check the signature, expiry, issuer and audience of a JWT
+    pub fn verify(&self, token: &str) -> Result<JwtClaims, AppError> {
+        let invalid = |reason: &str| {
+            tracing::warn!("Refused JWT: {}", reason);
+            AppError::Unauthorized
+        };
+
+        let header = decode_header(token).map_err(|e| invalid(&e.to_string()))?;
+        let key = match header.alg {
+            Algorithm::HS256 => self.secret.as_ref(),
+            Algorithm::RS256 | Algorithm::EdDSA => self
+                .public_keys
+                .iter()
+                .find(|(kid, algorithm, _)| {
+                    *algorithm == header.alg
+                        && (header.kid.is_none() || kid.as_deref() == header.kid.as_deref())
+                })
+                .map(|(_, _, key)| key),
+            _ => None,
+        }
+        .ok_or_else(|| invalid(&format!("no {:?} key matches the token", header.alg)))?;
+
+        // `exp` is required
+        let mut validation = Validation::new(header.alg);
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        decode::<JwtClaims>(token, key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| invalid(&e.to_string()))
+    }
+}
+
+impl fmt::Debug for JwtVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtVerifier")
+            .field("secret", &self.secret.is_some())
+            .field("public_keys", &self.public_keys.len())
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .finish()
+    }
+}
+
+/// Whether a bearer token looks like a JWT (three dot-separated parts)
+pub fn is_jwt(token: &str) -> bool {
+    token.split('.').count() == 3
+}
@@ -0,0 +1,187 @@
+use super::encoding::command_name;
+use crate::client::PoolStats;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use redis::{RedisError, Value};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Prometheus metrics of the proxy, served on `/metrics`
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    commands: IntCounterVec,
+    redis_errors: IntCounterVec,
+    retries: IntCounterVec,
+    reconnections: IntCounter,
+    rate_limited: IntCounter,
+    pool_connections: IntGaugeVec,
+    pool_size: IntGauge,
+    pool_waiters: IntGauge,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("stashless".to_string()), None)
+            .expect("valid metrics prefix");
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route and status"),
+                &["endpoint", "status"],
+            )
+            .unwrap(),
+            http_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "HTTP request latency by route",
+                ),
+                &["endpoint"],
+            )
+            .unwrap(),
+            commands: IntCounterVec::new(
+                Opts::new("redis_commands_total", "Commands sent to Redis"),
+                &["command"],
+            )
+            .unwrap(),
+            redis_errors: IntCounterVec::new(
+                Opts::new(
+                    "redis_errors_total",
+                    "Redis errors, by error reply code or client error kind",
+                ),
+                &["kind"],
+            )
+            .unwrap(),
+            retries: IntCounterVec::new(
+                Opts::new(
+                    "redis_retries_total",
+                    "Attempts made again after a connection error",
+                ),
+                &["operation"],
+            )
+            .unwrap(),
+            reconnections: IntCounter::new(
+                "redis_reconnections_total",
+                "Connections to Redis restored after a failure",
+            )
+            .unwrap(),
+            rate_limited: IntCounter::new(
+                "rate_limited_requests_total",
+                "Requests refused by a rate limit",
+            )
+            .unwrap(),
+            pool_connections: IntGaugeVec::new(
+                Opts::new("pool_connections", "Pooled Redis connections by state"),
+                &["state"],
+            )
+            .unwrap(),
+            pool_size: IntGauge::new("pool_size", "Maximum number of pooled connections").unwrap(),
+            pool_waiters: IntGauge::new("pool_waiters", "Requests waiting for a pooled connection")
+                .unwrap(),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_duration.clone()),
+            Box::new(metrics.commands.clone()),
+            Box::new(metrics.redis_errors.clone()),
+            Box::new(metrics.retries.clone()),
+            Box::new(metrics.reconnections.clone()),
+            Box::new(metrics.rate_limited.clone()),
+            Box::new(metrics.pool_connections.clone()),
+            Box::new(metrics.pool_size.clone()),
+            Box::new(metrics.pool_waiters.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metrics are registered once");
+        }
+        metrics
+    }
+}
+
+/// Count an HTTP request, `endpoint` being the route it matched
+pub fn record_request(endpoint: &str, status: u16, duration: Duration) {
+    METRICS
+        .http_requests
+        .with_label_values(&[endpoint, &status.to_string()])
+        .inc();
+    METRICS
+        .http_duration
+        .with_label_values(&[endpoint])
+        .observe(duration.as_secs_f64());
+}
+
+/// Count a command once Redis replied to it.
+///
+/// Commands Redis does not know are counted as `unknown`, so clients cannot
+/// create a label per made up name.
+pub fn record_command(cmd: &redis::Cmd, reply: &Value) {
+    let command = match reply {
+        Value::ServerError(e)
+            if e.details()
+                .is_some_and(|d| d.starts_with("unknown command")) =>
+        {
+            "unknown".to_string()
+        }
+        _ => String::from_utf8_lossy(command_name(cmd)).to_uppercase(),
+    };
+    METRICS.commands.with_label_values(&[&command]).inc();
+
+    if let Value::ServerError(e) = reply {
+        METRICS.redis_errors.with_label_values(&[e.code()]).inc();
+    }
+}
+
+/// Count an error raised by the client (I/O error, timeout, ...) or a reply
+/// turned into an error
+pub fn record_error(error: &RedisError) {
+    let kind = match error.code() {
+        Some(code) => code.to_string(),
+        None => format!("{:?}", error.kind()),
+    };
+    METRICS.redis_errors.with_label_values(&[&kind]).inc();
+}
+
+/// Count a retry of a command, pipeline or transaction
+pub fn record_retry(operation: &str) {
+    METRICS.retries.with_label_values(&[operation]).inc();
+}
+
+pub fn record_reconnection() {
+    METRICS.reconnections.inc();
+}
+
+pub fn record_rate_limited() {
+    METRICS.rate_limited.inc();
+}
+
+/// Prometheus text exposition of every metric, with the pool usage at the
+/// time of the scrape
+pub fn render(stats: PoolStats) -> String {
+    for (state, count) in [
+        ("open", stats.open),
+        ("idle", stats.idle),
+        ("in_use", stats.in_use),
+    ] {
+        METRICS
+            .pool_connections
+            .with_label_values(&[state])
+            .set(count as i64);
+    }
+    METRICS.pool_size.set(stats.size as i64);
+    METRICS.pool_waiters.set(stats.waiters as i64);
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&METRICS.registry.gather(), &mut buffer)
+        .expect("text encoding does not fail");
+    String::from_utf8(buffer).expect("text encoding is UTF-8")
+}
@@ -1,5 +1,13 @@
 pub mod auth;
+pub mod body;
+pub mod commands;
 pub mod encoding;
 pub mod errors;
+pub mod jwt;
+pub mod metrics;
+pub mod namespace;
+pub mod options;
+pub mod policy;
+pub mod resp;
 
 pub use errors::AppError;
@@ -0,0 +1,137 @@
+use super::commands::{channel_positions, key_positions};
+use redis::Value;
+
+/// Commands touching the whole keyspace, which a prefix cannot isolate.
+/// They are denied to tokens with a key prefix.
+pub const UNISOLATED_COMMANDS: &[&str] = &[
+    "FLUSHALL",
+    "FLUSHDB",
+    "RANDOMKEY",
+    "DBSIZE",
+    "SWAPDB",
+    "SELECT",
+    "MIGRATE",
+    "SORT",
+    "SORT_RO",
+    "PUBSUB",
+];
+
+/// Prefix the key and Pub/Sub channel arguments of a command (name
+/// included).
+///
+/// KEYS and SCAN have no key argument but a pattern, which is prefixed so
+/// that only the keys of the namespace are listed. Channel patterns of
+/// PSUBSCRIBE are prefixed the same way.
+pub fn prefix_keys(mut args: Vec<Vec<u8>>, prefix: &[u8]) -> Vec<Vec<u8>> {
+    let Some(name) = args.first().map(|name| name.to_ascii_uppercase()) else {
+        return args;
+    };
+    let patterns = matches!(name.as_slice(), b"PSUBSCRIBE" | b"PUNSUBSCRIBE");
+    for position in key_positions(&args) {
+        args[position].splice(0..0, prefix.iter().copied());
+    }
+    for position in channel_positions(&args) {
+        args[position] = if patterns {
+            prefix_pattern(prefix, &args[position])
+        } else {
+            [prefix, &args[position]].concat()
+        };
+    }
+
+    match name.as_slice() {
+        b"KEYS" => {
+            if let Some(pattern) = args.get_mut(1) {
+                *pattern = prefix_pattern(prefix, pattern);
+            }
+        }
+        b"SCAN" => {
+            // SCAN cursor [MATCH pattern] [COUNT count] [TYPE type], every
+            // option takes a value. Redis uses the last MATCH, all of them
+            // get the prefix.
+            let mut matched = false;
+            for at in (2..args.len().saturating_sub(1)).step_by(2) {
+                if args[at].eq_ignore_ascii_case(b"MATCH") {
+                    args[at + 1] = prefix_pattern(prefix, &args[at + 1]);
+                    matched = true;
+                }
+            }
+            if !matched {
+                args.extend([b"MATCH".to_vec(), prefix_pattern(prefix, b"*")]);
+            }
+        }
+        _ => {}
+    }
+    args
+}
+
+/// Remove the prefix from the key names found in a reply
+pub fn strip_keys(name: &[u8], value: Value, prefix: &[u8]) -> Value {
+    let strip = |value| strip_key(value, prefix);
+    match name.to_ascii_uppercase().as_slice() {
+        b"KEYS" => map_array(value, strip),
+        // [cursor, [key, ...]]
+        b"SCAN" => match value {
+            Value::Array(mut reply) if reply.len() == 2 => {
+                let keys = reply.pop().unwrap();
+                reply.push(map_array(keys, strip));
+                Value::Array(reply)
+            }
+            value => value,
+        },
+        // [key, element, ...]
+        b"BLPOP" | b"BRPOP" | b"BZPOPMIN" | b"BZPOPMAX" | b"LMPOP" | b"BLMPOP" | b"ZMPOP"
+        | b"BZMPOP" => strip_first(value, prefix),
+        // [[key, entries], ...], or a map of the entries by key in RESP3
+        b"XREAD" | b"XREADGROUP" => match value {
+            Value::Map(streams) => Value::Map(
+                streams
+                    .into_iter()
+                    .map(|(key, entries)| (strip(key), entries))
+                    .collect(),
+            ),
+            value => map_array(value, |stream| strip_first(stream, prefix)),
+        },
+        _ => value,
+    }
+}
+
+/// Glob pattern matching `pattern` inside the namespace, special characters
+/// of the prefix are escaped
+pub fn prefix_pattern(prefix: &[u8], pattern: &[u8]) -> Vec<u8> {
+    let mut prefixed = Vec::with_capacity(prefix.len() + pattern.len());
+    for &byte in prefix {
+        if matches!(byte, b'*' | b'?' | b'[' | b']' | b'\\') {
+            prefixed.push(b'\\');
+        }
+        prefixed.push(byte);
+    }
+    prefixed.extend_from_slice(pattern);
+    prefixed
+}
+
+fn strip_key(value: Value, prefix: &[u8]) -> Value {
+    match value {
+        Value::BulkString(key) if key.starts_with(prefix) => {
+            Value::BulkString(key[prefix.len()..].to_vec())
+        }
+        value => value,
+    }
+}
+
+fn strip_first(value: Value, prefix: &[u8]) -> Value {
+    match value {
+        Value::Array(mut values) if !values.is_empty() => {
+            let key = values.remove(0);
+            values.insert(0, strip_key(key, prefix));
+            Value::Array(values)
+        }
+        value => value,
+    }
+}
+
+fn map_array(value: Value, f: impl Fn(Value) -> Value) -> Value {
+    match value {
+        Value::Array(values) => Value::Array(values.into_iter().map(f).collect()),
+        value => value,
+    }
+}
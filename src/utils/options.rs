@@ -0,0 +1,134 @@
+use super::auth::{
+    check_encoding_header, check_protocol_header, check_read_primary_header,
+    check_request_encoding_header,
+};
+use super::body::{BodyFormat, BodyValue};
+use super::namespace::{prefix_keys, strip_keys};
+use super::policy::CommandPolicy;
+use super::resp::RESP_CONTENT_TYPE;
+use super::AppError;
+use axum::extract::Request;
+use axum::http::header;
+use axum::response::Response;
+
+/// Representation of the response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    /// JSON (Upstash-compatible), MessagePack or CBOR
+    Body(BodyFormat),
+    /// The Redis replies encoded back to RESP (`Accept: application/x-resp`)
+    Resp,
+}
+
+impl Default for ResponseFormat {
+    fn default() -> Self {
+        Self::Body(BodyFormat::Json)
+    }
+}
+
+impl ResponseFormat {
+    /// First supported format of the `Accept` header, otherwise the format of
+    /// the request body
+    fn from_request(request: &Request, body_format: BodyFormat) -> Self {
+        header_media_types(request, header::ACCEPT)
+            .find_map(|media_type| match media_type.as_str() {
+                RESP_CONTENT_TYPE => Some(Self::Resp),
+                media_type => BodyFormat::from_media_type(media_type).map(Self::Body),
+            })
+            .unwrap_or(Self::Body(body_format))
+    }
+
+    /// Whether bulk replies are sent as byte strings
+    pub fn has_bytes(self) -> bool {
+        matches!(self, Self::Body(format) if format.has_bytes())
+    }
+}
+
+/// Media types listed in a header, without their parameters
+fn header_media_types(
+    request: &Request,
+    name: header::HeaderName,
+) -> impl Iterator<Item = String> + '_ {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .split(',')
+        .map(|media| media.split(';').next().unwrap_or("").trim().to_lowercase())
+}
+
+/// Per-request behaviour selected by headers
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestOptions<'a> {
+    /// `upstash-encoding: base64`, strings in the response are base64 encoded
+    pub encoding_enabled: bool,
+    /// `slashless-request-encoding: base64`, arguments are base64 encoded
+    pub base64_args: bool,
+    /// `slashless-read-primary: true`, reads skip the replicas
+    pub read_primary: bool,
+    /// `slashless-protocol: resp3`, commands run over RESP3 and responses keep
+    /// its types
+    pub resp3: bool,
+    /// Encoding of the request body, from the `Content-Type` header
+    pub body_format: BodyFormat,
+    /// Selected by the `Accept` header, same as the request body by default
+    pub format: ResponseFormat,
+    /// Commands allowed for the token, all of them when not set
+    pub policy: Option<&'a CommandPolicy>,
+    /// Key prefix of the token, isolating its keys from other tokens
+    pub key_prefix: Option<&'a str>,
+}
+
+impl RequestOptions<'_> {
+    pub fn from_request(request: &Request) -> Self {
+        // Bodies without a supported content type are JSON, like before
+        let body_format = header_media_types(request, header::CONTENT_TYPE)
+            .find_map(|media_type| BodyFormat::from_media_type(&media_type))
+            .unwrap_or_default();
+        Self {
+            encoding_enabled: check_encoding_header(request),
+            base64_args: check_request_encoding_header(request),
+            read_primary: check_read_primary_header(request),
+            resp3: check_protocol_header(request),
+            body_format,
+            format: ResponseFormat::from_request(request, body_format),
+            policy: None,
+            key_prefix: None,
+        }
+    }
+
+    /// Refuse commands outside of the token policy
+    pub fn check_allowed(&self, args: &[Vec<u8>]) -> Result<(), AppError> {
+        match self.policy {
+            Some(policy) => policy.check(args),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply the key prefix of the token to a command (name included)
+    pub fn prefix_keys(&self, args: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        match self.key_prefix {
+            Some(prefix) => prefix_keys(args, prefix.as_bytes()),
+            None => args,
+        }
+    }
+
+    /// Remove the key prefix of the token from the reply of command `name`
+    pub fn strip_keys(&self, name: &[u8], value: redis::Value) -> redis::Value {
+        match self.key_prefix {
+            Some(prefix) => strip_keys(name, value, prefix.as_bytes()),
+            None => value,
+        }
+    }
+
+    /// Response with the body encoded in the response format
+    pub fn render(&self, body: BodyValue) -> Response {
+        match self.format {
+            ResponseFormat::Body(format) => body.render(format),
+            // Re-encoded RESP responses are built from the replies themselves, this
+            // is only reached for bodies made by stashless
+            ResponseFormat::Resp => body.render(BodyFormat::Json),
+        }
+    }
+}
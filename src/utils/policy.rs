@@ -0,0 +1,239 @@
+use super::commands::{channel_positions, command_keys, has_known_keys, in_category, CATEGORIES};
+use super::namespace::UNISOLATED_COMMANDS;
+use super::AppError;
+
+/// Commands a token may run: names (`GET`) or categories (`@dangerous`)
+#[derive(Debug, Clone, Default)]
+pub struct CommandPolicy {
+    /// Allowlists, a command has to be in each of them: the server or token
+    /// list, then the `commands` claim of a JWT
+    allow: Vec<Vec<String>>,
+    /// Refused even when allowed
+    deny: Vec<String>,
+    /// Read-only scope, anything outside `@read` is refused
+    read_only: bool,
+    /// When not empty, every key has to match one of these glob patterns
+    key_patterns: Vec<Vec<u8>>,
+    /// Refuse commands whose keys cannot be located, set for tokens with a
+    /// key prefix or key patterns
+    known_keys_only: bool,
+}
+
+impl CommandPolicy {
+    /// Policy from allow and deny lists, failing on unknown categories
+    pub fn new(allow: &[String], deny: &[String], read_only: bool) -> Result<Self, String> {
+        let allow = match allow {
+            [] => Vec::new(),
+            allow => vec![parse_rules(allow)?],
+        };
+        Ok(Self {
+            allow,
+            deny: parse_rules(deny)?,
+            read_only,
+            key_patterns: Vec::new(),
+            known_keys_only: false,
+        })
+    }
+
+    /// Same policy, only allowing the commands that are also in `allow`. An
+    /// empty list allows nothing.
+    pub fn restrict_allow(&self, allow: &[String]) -> Result<Self, String> {
+        let mut policy = self.clone();
+        policy.allow.push(parse_rules(allow)?);
+        Ok(policy)
+    }
+
+    /// Same policy for a token with a key prefix.
+    ///
+    /// Commands working on the whole keyspace, or whose keys cannot be
+    /// located, are denied: the prefix could not isolate them. Scripts are
+    /// denied too, they can reach any key by name.
+    pub fn with_key_prefix(&self) -> Self {
+        let mut deny = self.deny.clone();
+        deny.extend(
+            UNISOLATED_COMMANDS
+                .iter()
+                .chain(&["@scripting"])
+                .map(|name| name.to_lowercase()),
+        );
+        Self {
+            deny,
+            known_keys_only: true,
+            ..self.clone()
+        }
+    }
+
+    /// Same policy restricted to the keys matching one of `patterns`.
+    ///
+    /// Commands listing or wiping the keyspace are denied, they would reach
+    /// the other keys, and so are commands whose keys cannot be located.
+    /// Scripts are denied too: they can reach any key by name, whatever
+    /// their declared keys. Pub/Sub channels have to match the patterns as
+    /// well, so channel patterns (PSUBSCRIBE) are denied.
+    pub fn with_key_patterns(&self, patterns: &[String]) -> Self {
+        let mut deny = self.deny.clone();
+        deny.extend(
+            UNISOLATED_COMMANDS
+                .iter()
+                .chain(&["KEYS", "SCAN", "@scripting", "PSUBSCRIBE"])
+                .map(|name| name.to_lowercase()),
+        );
+        Self {
+            deny,
+            key_patterns: patterns.iter().map(|p| p.as_bytes().to_vec()).collect(),
+            known_keys_only: true,
+            ..self.clone()
+        }
+    }
+
+    /// Refuse a command (name included) outside of the policy, with a
+    /// Redis-like NOPERM error
+    pub fn check<A: AsRef<[u8]>>(&self, args: &[A]) -> Result<(), AppError> {
+        let Some(name) = args.first() else {
+            return Ok(());
+        };
+        let name = String::from_utf8_lossy(name.as_ref());
+
+        let allowed = (!self.read_only || in_category(&name, "read"))
+            && self
+                .allow
+                .iter()
+                .all(|allow| allow.iter().any(|rule| matches(rule, &name)))
+            && !self.deny.iter().any(|rule| matches(rule, &name))
+            && (!self.known_keys_only || has_known_keys(&name));
+        if !allowed {
+            return Err(AppError::Forbidden(format!(
+                "NOPERM this token has no permissions to run the '{}' command",
+                name.to_lowercase()
+            )));
+        }
+
+        if !self.key_patterns.is_empty() {
+            let keys = command_keys(args).into_iter().map(|key| (key, "key"));
+            let channels = channel_positions(args)
+                .into_iter()
+                .map(|position| (args[position].as_ref(), "channel"));
+            for (name, kind) in keys.chain(channels) {
+                if !self
+                    .key_patterns
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name))
+                {
+                    return Err(AppError::Forbidden(format!(
+                        "NOPERM this token has no permissions to access the '{}' {}",
+                        String::from_utf8_lossy(name),
+                        kind
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Comma-separated rule list, as in SLASHLESS_DENY_COMMANDS
+pub fn split_rules(rules: &str) -> Vec<String> {
+    rules
+        .split(',')
+        .map(|rule| rule.trim().to_string())
+        .filter(|rule| !rule.is_empty())
+        .collect()
+}
+
+fn parse_rules(rules: &[String]) -> Result<Vec<String>, String> {
+    rules
+        .iter()
+        .map(|rule| match rule.strip_prefix('@') {
+            Some(category) if !CATEGORIES.contains(&category.to_lowercase().as_str()) => {
+                Err(format!(
+                    "Unknown command category @{} (expected one of @{})",
+                    category,
+                    CATEGORIES.join(", @")
+                ))
+            }
+            _ => Ok(rule.to_lowercase()),
+        })
+        .collect()
+}
+
+fn matches(rule: &str, name: &str) -> bool {
+    match rule.strip_prefix('@') {
+        Some(category) => in_category(name, category),
+        None => rule.eq_ignore_ascii_case(name),
+    }
+}
+
+/// Redis glob matching, as for KEYS: `*`, `?`, `[abc]`, `[^a-z]` and `\` escapes.
+///
+/// Iterative, backtracking to the last `*` only, so any pattern runs in
+/// O(pattern × string).
+fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Pattern position after the last `*`, and the string position it resumes at
+    let mut star: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        if pattern.get(p) == Some(&b'*') {
+            while pattern.get(p) == Some(&b'*') {
+                p += 1;
+            }
+            star = Some((p, s));
+            continue;
+        }
+        if p < pattern.len() {
+            let (matched, next) = match_element(pattern, p, string[s]);
+            if matched {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+        // Let the last `*` swallow one more byte
+        match star {
+            Some((star_p, star_s)) => {
+                p = star_p;
+                s = star_s + 1;
+                star = Some((star_p, s));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
+
+/// Whether the pattern element at `p` (other than `*`) matches `byte`, and
+/// the position of the next element
+fn match_element(pattern: &[u8], p: usize, byte: u8) -> (bool, usize) {
+    match pattern[p] {
+        b'?' => (true, p + 1),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == byte, p + 2),
+        b'[' => {
+            let mut i = p + 1;
+            let negate = pattern.get(i) == Some(&b'^');
+            if negate {
+                i += 1;
+            }
+            let mut found = false;
+            while i < pattern.len() && pattern[i] != b']' {
+                if pattern[i] == b'\\' && i + 1 < pattern.len() {
+                    found |= pattern[i + 1] == byte;
+                    i += 2;
+                } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']'
+                {
+                    let (low, high) = (
+                        pattern[i].min(pattern[i + 2]),
+                        pattern[i].max(pattern[i + 2]),
+                    );
+                    found |= (low..=high).contains(&byte);
+                    i += 3;
+                } else {
+                    found |= pattern[i] == byte;
+                    i += 1;
+                }
+            }
+            // An unterminated class matches like Redis, up to the end of the pattern
+            (found != negate, (i + 1).min(pattern.len()))
+        }
+        literal => (literal == byte, p + 1),
+    }
+}
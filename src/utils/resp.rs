@@ -0,0 +1,107 @@
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use redis::Value;
+
+/// Content type of re-encoded RESP responses
+pub const RESP_CONTENT_TYPE: &str = "application/x-resp";
+
+/// Response made of each reply encoded again in RESP, back to back as Redis
+/// sends them on a pipelined connection
+pub fn resp_response(values: &[Value], resp3: bool) -> Response {
+    let mut body = Vec::new();
+    for value in values {
+        write_value(&mut body, value, resp3);
+    }
+    ([(header::CONTENT_TYPE, RESP_CONTENT_TYPE)], body).into_response()
+}
+
+/// Encode a parsed reply back to RESP.
+///
+/// Replies come from a connection speaking the same protocol, so RESP3 types
+/// only show up when `resp3` is set. Nil is the only type that has a different
+/// encoding per protocol.
+///
+/// The parser does not keep the original bytes, so a few replies come out
+/// differently than Redis wrote them: a RESP2 null array (`*-1`) is written
+/// as a null bulk string (`$-1`), and doubles in plain decimal notation
+/// (`1e+300` becomes `1000...0`).
+fn write_value(out: &mut Vec<u8>, value: &Value, resp3: bool) {
+    match value {
+        Value::Nil if resp3 => out.extend_from_slice(b"_\r\n"),
+        Value::Nil => out.extend_from_slice(b"$-1\r\n"),
+        Value::Int(i) => write_line(out, b':', i.to_string().as_bytes()),
+        Value::BulkString(bytes) => write_blob(out, b'$', bytes),
+        Value::Array(values) => write_aggregate(out, b'*', values, resp3),
+        Value::SimpleString(s) => write_line(out, b'+', s.as_bytes()),
+        Value::Okay => out.extend_from_slice(b"+OK\r\n"),
+        Value::Map(pairs) => {
+            write_line(out, b'%', pairs.len().to_string().as_bytes());
+            for (key, value) in pairs {
+                write_value(out, key, resp3);
+                write_value(out, value, resp3);
+            }
+        }
+        Value::Attribute { data, attributes } => {
+            write_line(out, b'|', attributes.len().to_string().as_bytes());
+            for (key, value) in attributes {
+                write_value(out, key, resp3);
+                write_value(out, value, resp3);
+            }
+            write_value(out, data, resp3);
+        }
+        Value::Set(values) => write_aggregate(out, b'~', values, resp3),
+        Value::Double(d) => {
+            let d = match d {
+                d if d.is_nan() => "nan".to_string(),
+                d if d.is_infinite() && *d > 0.0 => "inf".to_string(),
+                d if d.is_infinite() => "-inf".to_string(),
+                d => d.to_string(),
+            };
+            write_line(out, b',', d.as_bytes());
+        }
+        Value::Boolean(b) => out.extend_from_slice(if *b { b"#t\r\n" } else { b"#f\r\n" }),
+        Value::VerbatimString { format, text } => {
+            write_blob(out, b'=', format!("{}:{}", format, text).as_bytes())
+        }
+        Value::BigNumber(n) => write_line(out, b'(', n.to_string().as_bytes()),
+        Value::Push { kind, data } => {
+            write_line(out, b'>', (data.len() + 1).to_string().as_bytes());
+            write_blob(out, b'$', kind.to_string().as_bytes());
+            for value in data {
+                write_value(out, value, resp3);
+            }
+        }
+        Value::ServerError(e) => write_line(
+            out,
+            b'-',
+            format_server_error(e.code(), e.details()).as_bytes(),
+        ),
+    }
+}
+
+/// Message of an error reply, as Redis sends it (`WRONGTYPE Operation ...`)
+pub fn format_server_error(code: &str, details: Option<&str>) -> String {
+    match details {
+        Some(details) => format!("{} {}", code, details),
+        None => code.to_string(),
+    }
+}
+
+fn write_line(out: &mut Vec<u8>, prefix: u8, line: &[u8]) {
+    out.push(prefix);
+    out.extend_from_slice(line);
+    out.extend_from_slice(b"\r\n");
+}
+
+fn write_blob(out: &mut Vec<u8>, prefix: u8, bytes: &[u8]) {
+    write_line(out, prefix, bytes.len().to_string().as_bytes());
+    out.extend_from_slice(bytes);
+    out.extend_from_slice(b"\r\n");
+}
+
+fn write_aggregate(out: &mut Vec<u8>, prefix: u8, values: &[Value], resp3: bool) {
+    write_line(out, prefix, values.len().to_string().as_bytes());
+    for value in values {
+        write_value(out, value, resp3);
+    }
+}
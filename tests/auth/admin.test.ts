@@ -0,0 +1,64 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+// SLASHLESS_ADMIN_TOKEN of the server, and its URL when SLASHLESS_ADMIN_PORT is set
+const ADMIN_TOKEN = Bun.env.ADMIN_TOKEN;
+const ADMIN_URL = Bun.env.ADMIN_URL ?? `${BASE_URL}/admin`;
+
+describe.skipIf(!ADMIN_TOKEN)("Admin API", () => {
+  const admin = (path: string, init: RequestInit = {}, token = ADMIN_TOKEN) =>
+    fetch(`${ADMIN_URL}${path}`, {
+      ...init,
+      headers: { Authorization: `Bearer ${token}` },
+    });
+
+  test("should refuse API tokens", async () => {
+    const response = await admin("/status", {}, DEFAULT_TOKEN);
+    expect(response.status).toBe(401);
+  });
+
+  test("should report the pool status", async () => {
+    const response = await admin("/status");
+    expect(response.status).toBe(200);
+    const { result } = await response.json();
+    expect(result.maintenance).toBe(false);
+    expect(result.redis.pool.size).toBeGreaterThan(0);
+  });
+
+  test("should list tokens without their secrets", async () => {
+    const { result } = await (await admin("/tokens")).json();
+    expect(Array.isArray(result)).toBe(true);
+    for (const token of result) {
+      expect(token.name).toBeString();
+      expect(token.token).toBeUndefined();
+    }
+  });
+
+  test("should answer 503 in maintenance mode", async () => {
+    const enable = await admin("/maintenance", {
+      method: "PUT",
+      body: JSON.stringify({ enabled: true }),
+    });
+    expect(enable.status).toBe(200);
+
+    try {
+      const response = await fetch(BASE_URL, {
+        method: "POST",
+        headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+        body: JSON.stringify(["PING"]),
+      });
+      expect(response.status).toBe(503);
+      expect((await fetch(`${BASE_URL}/health`)).status).toBe(200);
+    } finally {
+      await admin("/maintenance", {
+        method: "PUT",
+        body: JSON.stringify({ enabled: false }),
+      });
+    }
+  });
+
+  test("should reconnect to Redis", async () => {
+    const { result } = await (await admin("/reconnect", { method: "POST" })).json();
+    expect(result).toBe("PONG");
+  });
+});
@@ -0,0 +1,33 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+async function post(path: string, body: unknown) {
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(body),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+// Run against the default policy (SLASHLESS_DENY_COMMANDS unset)
+describe("Command policy", () => {
+  test("should refuse dangerous commands by default", async () => {
+    for (const command of [["FLUSHALL"], ["KEYS", "*"], ["CONFIG", "GET", "maxmemory"]]) {
+      const result = await post("/", command);
+      expect(result.status).toBe(403);
+      expect(result.body.error).toStartWith("NOPERM");
+    }
+  });
+
+  test("should refuse pipelines and transactions containing a denied command", async () => {
+    expect((await post("/pipeline", [["PING"], ["FLUSHDB"]])).status).toBe(403);
+    expect((await post("/multi-exec", [["PING"], ["DEBUG", "SLEEP", "0"]])).status).toBe(403);
+  });
+
+  test("should run other commands", async () => {
+    const result = await post("/", ["PING"]);
+    expect(result.status).toBe(200);
+    expect(result.body.result).toBe("PONG");
+  });
+});
@@ -0,0 +1,82 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL } from "../helpers/client";
+
+// Same value as SLASHLESS_JWT_SECRET
+const JWT_SECRET = Bun.env.JWT_SECRET;
+
+function base64url(data: string | ArrayBuffer): string {
+  return Buffer.from(data as string).toString("base64url");
+}
+
+async function sign(claims: Record<string, unknown>): Promise<string> {
+  const message = `${base64url(JSON.stringify({ alg: "HS256", typ: "JWT" }))}.${base64url(JSON.stringify(claims))}`;
+  const key = await crypto.subtle.importKey(
+    "raw",
+    new TextEncoder().encode(JWT_SECRET),
+    { name: "HMAC", hash: "SHA-256" },
+    false,
+    ["sign"],
+  );
+  const signature = await crypto.subtle.sign("HMAC", key, new TextEncoder().encode(message));
+  return `${message}.${Buffer.from(signature).toString("base64url")}`;
+}
+
+async function run(token: string, command: unknown[]) {
+  const response = await fetch(BASE_URL, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${token}` },
+    body: JSON.stringify(command),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+const now = () => Math.floor(Date.now() / 1000);
+
+describe.skipIf(!JWT_SECRET)("JWT authentication", () => {
+  test("should accept a valid JWT", async () => {
+    const token = await sign({ sub: "test", exp: now() + 60 });
+    const result = await run(token, ["PING"]);
+    expect(result.status).toBe(200);
+    expect(result.body.result).toBe("PONG");
+  });
+
+  test("should refuse expired or tampered JWTs", async () => {
+    const expired = await sign({ sub: "test", exp: now() - 3600 });
+    expect((await run(expired, ["PING"])).status).toBe(401);
+
+    const token = await sign({ sub: "test", exp: now() + 60 });
+    expect((await run(`${token}x`, ["PING"])).status).toBe(401);
+  });
+
+  test("should restrict commands and keys to the claims", async () => {
+    const token = await sign({ sub: "test", exp: now() + 60, commands: ["GET", "SET"], keys: ["test:jwt:*"] });
+    expect((await run(token, ["SET", "test:jwt:key", "value"])).status).toBe(200);
+    expect((await run(token, ["GET", "test:jwt:key"])).body.result).toBe("value");
+    expect((await run(token, ["GET", "test:other"])).status).toBe(403);
+    expect((await run(token, ["DEL", "test:jwt:key"])).status).toBe(403);
+  });
+
+  test("should refuse scripts when keys are restricted", async () => {
+    const token = await sign({ sub: "test", exp: now() + 60, keys: ["test:jwt:*"] });
+    const script = "return redis.call('GET', 'test:other')";
+    expect((await run(token, ["EVAL", script, "0"])).status).toBe(403);
+    expect((await run(token, ["EVAL_RO", script, "0"])).status).toBe(403);
+  });
+
+  test("should restrict Pub/Sub channels to the key patterns", async () => {
+    const token = await sign({ sub: "test", exp: now() + 60, keys: ["test:jwt:*"] });
+    expect((await run(token, ["PUBLISH", "test:jwt:news", "hi"])).status).toBe(200);
+    expect((await run(token, ["PUBLISH", "test:other", "hi"])).status).toBe(403);
+
+    const headers = { Authorization: `Bearer ${token}` };
+    const other = await fetch(`${BASE_URL}/subscribe/test:other`, { headers });
+    expect(other.status).toBe(403);
+    const pattern = await fetch(`${BASE_URL}/psubscribe/test:jwt:*`, { headers });
+    expect(pattern.status).toBe(403);
+  });
+
+  test("should allow nothing with an empty commands claim", async () => {
+    const token = await sign({ sub: "test", exp: now() + 60, commands: [] });
+    expect((await run(token, ["PING"])).status).toBe(403);
+  });
+});
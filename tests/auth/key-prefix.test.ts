@@ -0,0 +1,124 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { createRedisClient, BASE_URL } from "../helpers/client";
+
+// Tokens listed in SLASHLESS_TOKENS with different "prefix" values
+const FIRST_TOKEN = Bun.env.PREFIXED_TOKEN;
+const SECOND_TOKEN = Bun.env.OTHER_PREFIXED_TOKEN;
+
+describe.skipIf(!FIRST_TOKEN || !SECOND_TOKEN)("Key prefixes", () => {
+  const first = createRedisClient(BASE_URL, FIRST_TOKEN);
+  const second = createRedisClient(BASE_URL, SECOND_TOKEN);
+
+  afterAll(async () => {
+    await first.del("test:prefix", "test:prefix:other", "test:prefix:geo", "test:prefix:near");
+    await second.del("test:prefix");
+  });
+
+  test("should keep the keys of each token apart", async () => {
+    await first.set("test:prefix", "first");
+    await second.set("test:prefix", "second");
+
+    expect(await first.get("test:prefix")).toBe("first");
+    expect(await second.get("test:prefix")).toBe("second");
+  });
+
+  test("should prefix every key of multi-key commands", async () => {
+    await first.set("test:prefix:other", "other");
+    expect(await first.mget("test:prefix", "test:prefix:other")).toEqual(["first", "other"]);
+  });
+
+  test("should prefix both keys of LCS and the STORE key of GEORADIUS", async () => {
+    await first.set("test:prefix", "ohmytext");
+    await first.set("test:prefix:other", "mynewtext");
+    const lcs = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${FIRST_TOKEN}` },
+      body: JSON.stringify(["LCS", "test:prefix", "test:prefix:other"]),
+    });
+    expect(await lcs.json()).toEqual({ result: "mytext" });
+
+    await first.geoadd("test:prefix:geo", {
+      longitude: 13.361389,
+      latitude: 38.115556,
+      member: "Palermo",
+    });
+    const georadius = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${FIRST_TOKEN}` },
+      body: JSON.stringify(
+        ["GEORADIUS", "test:prefix:geo", "15", "37", "200", "km", "STORE", "test:prefix:near"],
+      ),
+    });
+    expect(await georadius.json()).toEqual({ result: 1 });
+    expect(await first.zcard("test:prefix:near")).toBe(1);
+    expect(await second.exists("test:prefix:near")).toBe(0);
+  });
+
+  test("should refuse commands with unknown key positions", async () => {
+    const response = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${FIRST_TOKEN}` },
+      body: JSON.stringify(["BF.ADD", "test:prefix:bloom", "item"]),
+    });
+    expect(response.status).toBe(403);
+  });
+
+  test.each([
+    [["EVAL", "return redis.call('GET', 'test:prefix')", "0"]],
+    [["EVALSHA", "0000000000000000000000000000000000000000", "0"]],
+    [["FCALL", "noop", "0"]],
+    [["SCRIPT", "FLUSH"]],
+  ])("should refuse %p, scripts can reach any key", async (command) => {
+    const response = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${FIRST_TOKEN}` },
+      body: JSON.stringify(command),
+    });
+    expect(response.status).toBe(403);
+  });
+
+  test("should prefix Pub/Sub channels", async () => {
+    const response = await fetch(`${BASE_URL}/subscribe/test:prefix:news`, {
+      headers: { Authorization: `Bearer ${FIRST_TOKEN}` },
+    });
+    const reader = response.body!.getReader();
+    await Bun.sleep(200);
+
+    // Same channel name from another tenant: not delivered
+    await second.publish("test:prefix:news", "other");
+    await first.publish("test:prefix:news", "hello");
+
+    let text = "";
+    while (!text.includes("hello")) {
+      const { value, done } = await reader.read();
+      if (done) break;
+      text += new TextDecoder().decode(value);
+    }
+    await reader.cancel();
+    expect(text).toContain("data: subscribe,test:prefix:news,1");
+    expect(text).toContain("data: message,test:prefix:news,hello");
+    expect(text).not.toContain("other");
+  });
+
+  test("should list only the keys of the token, without the prefix", async () => {
+    const [, keys] = await second.scan(0, { match: "test:prefix*" });
+    expect(keys).toEqual(["test:prefix"]);
+  });
+
+  test("should prefix every MATCH pattern of SCAN", async () => {
+    await first.set("test:prefix:other", "first only");
+    await second.set("test:prefix", "second");
+
+    // Redis applies the last MATCH, it must not reach other tokens' keys
+    const response = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${SECOND_TOKEN}` },
+      body: JSON.stringify(["SCAN", "0", "MATCH", "nothing", "MATCH", "*", "COUNT", "1000"]),
+    });
+    const { result } = await response.json();
+    expect(result[1]).toContain("test:prefix");
+    for (const key of result[1]) {
+      expect(key).not.toContain("test:prefix:other");
+    }
+  });
+});
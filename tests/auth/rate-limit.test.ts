@@ -0,0 +1,31 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL } from "../helpers/client";
+
+// Token listed in SLASHLESS_TOKENS with a small "rate_limit", e.g. "5/m"
+const LIMITED_TOKEN = Bun.env.LIMITED_TOKEN;
+
+describe.skipIf(!LIMITED_TOKEN)("Rate limiting", () => {
+  const ping = () =>
+    fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${LIMITED_TOKEN}` },
+      body: JSON.stringify(["PING"]),
+    });
+
+  test("should send the rate limit headers", async () => {
+    const response = await ping();
+    expect(response.headers.get("X-RateLimit-Limit")).not.toBeNull();
+    expect(response.headers.get("X-RateLimit-Remaining")).not.toBeNull();
+    expect(response.headers.get("X-RateLimit-Reset")).not.toBeNull();
+  });
+
+  test("should refuse requests over the limit with a 429", async () => {
+    let response = await ping();
+    while (response.status === 200) {
+      response = await ping();
+    }
+    expect(response.status).toBe(429);
+    expect(Number(response.headers.get("Retry-After"))).toBeGreaterThan(0);
+    expect(response.headers.get("X-RateLimit-Remaining")).toBe("0");
+  });
+});
@@ -0,0 +1,39 @@
+import { test, expect, describe } from "bun:test";
+import { createRedisClient, BASE_URL } from "../helpers/client";
+
+// Token listed in SLASHLESS_TOKENS with "scope": "read-only"
+const READONLY_TOKEN = Bun.env.READONLY_TOKEN;
+
+describe.skipIf(!READONLY_TOKEN)("Token scopes", () => {
+  test("should run read commands with a read-only token", async () => {
+    const redis = createRedisClient(BASE_URL, READONLY_TOKEN);
+    expect(await redis.ping()).toBe("PONG");
+    expect(await redis.get("test:scopes:missing")).toBeNull();
+  });
+
+  test("should refuse write commands with a read-only token", async () => {
+    const response = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${READONLY_TOKEN}` },
+      body: JSON.stringify(["SET", "test:scopes", "value"]),
+    });
+    expect(response.status).toBe(403);
+    expect((await response.json()).error).toStartWith("NOPERM");
+  });
+
+  test("should refuse pipelines containing a write command", async () => {
+    const response = await fetch(`${BASE_URL}/pipeline`, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${READONLY_TOKEN}` },
+      body: JSON.stringify([["GET", "test:scopes"], ["DEL", "test:scopes"]]),
+    });
+    expect(response.status).toBe(403);
+  });
+
+  test("should hide the script cache from a read-only token", async () => {
+    const response = await fetch(`${BASE_URL}/scripts`, {
+      headers: { Authorization: `Bearer ${READONLY_TOKEN}` },
+    });
+    expect(response.status).toBe(403);
+  });
+});
@@ -0,0 +1,85 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+const ROUNDS = 50;
+const KEY = Buffer.from("binary:fuzz").toString("base64");
+
+function randomBytes(): Buffer {
+  const bytes = Buffer.alloc(Math.floor(Math.random() * 256));
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = Math.floor(Math.random() * 256);
+  }
+  return bytes;
+}
+
+async function post(path: string, body: unknown, responseBase64 = false) {
+  const headers: Record<string, string> = {
+    Authorization: `Bearer ${DEFAULT_TOKEN}`,
+    "Content-Type": "application/json",
+    "slashless-request-encoding": "base64",
+  };
+  if (responseBase64) {
+    headers["upstash-encoding"] = "base64";
+  }
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers,
+    body: JSON.stringify(body),
+  });
+  return response.json();
+}
+
+const b64 = (s: string) => Buffer.from(s).toString("base64");
+
+/** Bytes of a result without `upstash-encoding`: a string, or {"base64": ...} */
+function resultBytes(result: string | { base64: string }): Buffer {
+  return typeof result === "string"
+    ? Buffer.from(result, "utf8")
+    : Buffer.from(result.base64, "base64");
+}
+
+describe("Binary responses", () => {
+  afterAll(async () => {
+    await post("/", [b64("DEL"), KEY]);
+  });
+
+  test("should round-trip random bytes through a single command", async () => {
+    for (let round = 0; round < ROUNDS; round++) {
+      const value = randomBytes();
+      await post("/", [b64("SET"), KEY, value.toString("base64")]);
+
+      const plain = await post("/", [b64("GET"), KEY]);
+      expect(resultBytes(plain.result).equals(value)).toBe(true);
+
+      const encoded = await post("/", [b64("GET"), KEY], true);
+      expect(Buffer.from(encoded.result, "base64").equals(value)).toBe(true);
+    }
+  });
+
+  test("should round-trip random bytes through pipelines and transactions", async () => {
+    for (let round = 0; round < ROUNDS; round++) {
+      const value = randomBytes();
+      const commands = [
+        [b64("SET"), KEY, value.toString("base64")],
+        [b64("GET"), KEY],
+      ];
+
+      for (const path of ["/pipeline", "/multi-exec"]) {
+        const plain = await post(path, commands);
+        expect(resultBytes(plain[1].result).equals(value)).toBe(true);
+
+        const encoded = await post(path, commands, true);
+        expect(Buffer.from(encoded[1].result, "base64").equals(value)).toBe(true);
+      }
+    }
+  });
+
+  test("should tag binary values instead of returning ambiguous strings", async () => {
+    await post("/", [b64("SET"), KEY, Buffer.from([0xff, 0xfe]).toString("base64")]);
+    expect(await post("/", [b64("GET"), KEY])).toEqual({ result: { base64: "//4=" } });
+
+    // A UTF-8 value that looks like base64 stays a plain string
+    await post("/", [b64("SET"), KEY, b64("//4=")]);
+    expect(await post("/", [b64("GET"), KEY])).toEqual({ result: "//4=" });
+  });
+});
@@ -0,0 +1,85 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless in Redis Cluster mode, seeded with the nodes of a three-node cluster
+const CLUSTER_SERVER_URL = Bun.env.CLUSTER_SERVER_URL;
+
+// Enough keys to land on every node
+const KEYS = Array.from({ length: 12 }, (_, i) => `cluster:key:${i}`);
+
+async function post(path: string, body: unknown) {
+  const response = await fetch(`${CLUSTER_SERVER_URL}${path}`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(body),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe.skipIf(!CLUSTER_SERVER_URL)("Redis Cluster", () => {
+  afterAll(async () => {
+    await post("/pipeline", [...KEYS, "{cluster:user}:name", "{cluster:user}:visits"].map((key) => ["DEL", key]));
+  });
+
+  test("should route each command to the node owning its key", async () => {
+    for (const key of KEYS) {
+      expect(await post("/", ["SET", key, key])).toEqual({ status: 200, body: { result: "OK" } });
+    }
+    for (const key of KEYS) {
+      expect((await post("/", ["GET", key])).body).toEqual({ result: key });
+    }
+  });
+
+  test("should answer pipelines spanning several nodes in order", async () => {
+    const { status, body } = await post(
+      "/pipeline",
+      KEYS.flatMap((key, i) => [
+        ["SET", key, String(i)],
+        ["INCR", key],
+      ]),
+    );
+    expect(status).toBe(200);
+    expect(body).toEqual(KEYS.flatMap((_, i) => [{ result: "OK" }, { result: i + 1 }]));
+  });
+
+  test("should run pipeline commands spanning several slots in request order", async () => {
+    const [first, second] = KEYS;
+    for (let i = 0; i < 10; i++) {
+      const { status, body } = await post("/pipeline", [
+        ["SET", first, "1"],
+        ["SET", second, "2"],
+        ["DEL", first, second],
+        ["GET", first],
+        ["EXISTS", first, second],
+        ["SET", second, "3"],
+        ["GET", second],
+      ]);
+      expect(status).toBe(200);
+      expect(body).toEqual([
+        { result: "OK" },
+        { result: "OK" },
+        { result: 2 },
+        { result: null },
+        { result: 0 },
+        { result: "OK" },
+        { result: "3" },
+      ]);
+    }
+  });
+
+  test("should run transactions whose keys share a hash tag", async () => {
+    const { status, body } = await post("/multi-exec", [
+      ["SET", "{cluster:user}:name", "ada"],
+      ["INCR", "{cluster:user}:visits"],
+      ["GET", "{cluster:user}:name"],
+    ]);
+    expect(status).toBe(200);
+    expect(body).toEqual([{ result: "OK" }, { result: 1 }, { result: "ada" }]);
+  });
+
+  test("should reject transactions spanning several slots", async () => {
+    const { status, body } = await post("/multi-exec", KEYS.map((key) => ["INCR", key]));
+    expect(status).toBe(400);
+    expect(body.error).toStartWith("CROSSSLOT");
+  });
+});
@@ -0,0 +1,48 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+async function post(path: string, body: unknown) {
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(body),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe("Connection state commands", () => {
+  afterAll(async () => {
+    await post("/", ["DEL", "state:key"]);
+  });
+
+  test.each([
+    [["SELECT", "1"]],
+    [["MULTI"]],
+    [["WATCH", "state:key"]],
+    [["AUTH", "default", "password"]],
+    [["HELLO", "3"]],
+    [["RESET"]],
+    [["QUIT"]],
+  ])("should refuse %p as a single command", async (command) => {
+    const { status, body } = await post("/", command);
+    expect(status).toBe(400);
+    expect(body.error).toContain("changes the state of the Redis connection");
+  });
+
+  test("should refuse them in pipelines", async () => {
+    const { status } = await post("/pipeline", [["SET", "state:key", "1"], ["SELECT", "1"]]);
+    expect(status).toBe(400);
+  });
+
+  test("should leave the pooled connections usable", async () => {
+    // A MULTI left open would make the next commands reply QUEUED
+    await post("/", ["MULTI"]);
+    await post("/pipeline", [["MULTI"]]);
+    for (let i = 0; i < 5; i++) {
+      expect(await post("/", ["SET", "state:key", String(i)])).toEqual({
+        status: 200,
+        body: { result: "OK" },
+      });
+    }
+  });
+});
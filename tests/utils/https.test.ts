@@ -0,0 +1,68 @@
+import { test, expect, describe } from "bun:test";
+import { copyFileSync, readFileSync } from "node:fs";
+import { connect } from "node:tls";
+import { DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless serving HTTPS with SLASHLESS_TLS_CERT and SLASHLESS_TLS_KEY, and
+// the CA that signed its certificate
+const HTTPS_SERVER_URL = Bun.env.HTTPS_SERVER_URL;
+const HTTPS_CA_CERT = Bun.env.HTTPS_CA_CERT;
+// The served certificate files, and a second pair copied over them to check
+// that the certificate is reloaded
+const HTTPS_CERT = Bun.env.HTTPS_CERT;
+const HTTPS_KEY = Bun.env.HTTPS_KEY;
+const HTTPS_RELOAD_CERT = Bun.env.HTTPS_RELOAD_CERT;
+const HTTPS_RELOAD_KEY = Bun.env.HTTPS_RELOAD_KEY;
+
+/** Common name of the certificate the server presents */
+function servedCommonName(): Promise<string> {
+  const { hostname, port } = new URL(HTTPS_SERVER_URL!);
+  return new Promise((resolve, reject) => {
+    const socket = connect({ host: hostname, port: Number(port), ca: readFileSync(HTTPS_CA_CERT!) }, () => {
+      const name = socket.getPeerCertificate().subject.CN;
+      socket.end();
+      resolve(name);
+    });
+    socket.on("error", reject);
+  });
+}
+
+describe.skipIf(!HTTPS_SERVER_URL || !HTTPS_CA_CERT)("HTTPS listener", () => {
+  test("should answer requests over HTTPS", async () => {
+    const response = await fetch(HTTPS_SERVER_URL!, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+      body: JSON.stringify(["PING"]),
+      tls: { ca: readFileSync(HTTPS_CA_CERT!, "utf8") },
+    });
+    expect(response.status).toBe(200);
+    expect(await response.json()).toEqual({ result: "PONG" });
+  });
+
+  test("should refuse plain HTTP", async () => {
+    const plain = HTTPS_SERVER_URL!.replace("https://", "http://");
+    const result = await fetch(plain).then(
+      (response) => response.status,
+      () => "refused",
+    );
+    expect(result).not.toBe(200);
+  });
+
+  test.skipIf(!HTTPS_CERT || !HTTPS_KEY || !HTTPS_RELOAD_CERT || !HTTPS_RELOAD_KEY)(
+    "should reload the certificate when its files change",
+    async () => {
+      const before = await servedCommonName();
+      copyFileSync(HTTPS_RELOAD_KEY!, HTTPS_KEY!);
+      copyFileSync(HTTPS_RELOAD_CERT!, HTTPS_CERT!);
+
+      // The files are checked every 10 seconds
+      let after = before;
+      for (let i = 0; i < 30 && after === before; i++) {
+        await Bun.sleep(1000);
+        after = await servedCommonName();
+      }
+      expect(after).not.toBe(before);
+    },
+    40000,
+  );
+});
@@ -0,0 +1,39 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN, redis } from "../helpers/client";
+
+async function scrape(): Promise<string> {
+  const response = await fetch(`${BASE_URL}/metrics`, {
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+  });
+  expect(response.status).toBe(200);
+  expect(response.headers.get("content-type")).toStartWith("text/plain");
+  return response.text();
+}
+
+describe("Prometheus metrics", () => {
+  test("should count requests per endpoint and commands", async () => {
+    await redis.set("test:metrics", "1");
+    await redis.get("test:metrics");
+    await redis.del("test:metrics");
+
+    const metrics = await scrape();
+    expect(metrics).toContain('stashless_http_requests_total{endpoint="/",status="200"}');
+    expect(metrics).toContain('stashless_http_request_duration_seconds_bucket{endpoint="/"');
+    expect(metrics).toContain('stashless_redis_commands_total{command="GET"}');
+    expect(metrics).toContain('stashless_redis_commands_total{command="SET"}');
+  });
+
+  test("should report the pool usage", async () => {
+    const metrics = await scrape();
+    expect(metrics).toMatch(/^stashless_pool_size \d+$/m);
+    expect(metrics).toContain('stashless_pool_connections{state="in_use"}');
+  });
+
+  test("should count error replies by code", async () => {
+    await redis.set("test:metrics:string", "value");
+    await expect(redis.lpush("test:metrics:string", "item")).rejects.toThrow();
+    await redis.del("test:metrics:string");
+
+    expect(await scrape()).toContain('stashless_redis_errors_total{kind="WRONGTYPE"}');
+  });
+});
@@ -0,0 +1,105 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+// Minimal CBOR encoder for command arrays of text and byte strings
+function cborHead(major: number, length: number): number[] {
+  return length < 24 ? [(major << 5) | length] : [(major << 5) | 24, length];
+}
+
+function cbor(value: string | Uint8Array | unknown[]): Uint8Array {
+  if (typeof value === "string") {
+    const bytes = new TextEncoder().encode(value);
+    return new Uint8Array([...cborHead(3, bytes.length), ...bytes]);
+  }
+  if (value instanceof Uint8Array) {
+    return new Uint8Array([...cborHead(2, value.length), ...value]);
+  }
+  const items = value.map((item) => [...cbor(item as string | Uint8Array | unknown[])]);
+  return new Uint8Array([...cborHead(4, value.length), ...items.flat()]);
+}
+
+async function send(path: string, body: Uint8Array | string, headers: Record<string, string>) {
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}`, ...headers },
+    body,
+  });
+  return {
+    status: response.status,
+    contentType: response.headers.get("content-type"),
+    body: new Uint8Array(await response.arrayBuffer()),
+  };
+}
+
+const binary = new Uint8Array([0x00, 0xff, 0x01]);
+
+describe("MessagePack & CBOR bodies", () => {
+  afterAll(async () => {
+    await send("/", JSON.stringify(["DEL", "cbor:key", "msgpack:key"]), {
+      "Content-Type": "application/json",
+    });
+  });
+
+  test("should accept CBOR byte string arguments and return byte strings", async () => {
+    const set = await send("/", cbor(["SET", "cbor:key", binary]), {
+      "Content-Type": "application/cbor",
+    });
+    expect(set.contentType).toBe("application/cbor");
+    // {"result": "OK"}
+    expect([...set.body]).toEqual([0xa1, ...cbor("result"), ...cbor("OK")]);
+
+    const get = await send("/", cbor(["GET", "cbor:key"]), {
+      "Content-Type": "application/cbor",
+    });
+    // {"result": h'00FF01'}
+    expect([...get.body]).toEqual([0xa1, ...cbor("result"), ...cbor(binary)]);
+  });
+
+  test("should answer in the format of the Accept header", async () => {
+    const result = await send("/", cbor(["GET", "cbor:key"]), {
+      "Content-Type": "application/cbor",
+      Accept: "application/json",
+    });
+    expect(result.contentType).toBe("application/json");
+    expect(JSON.parse(new TextDecoder().decode(result.body))).toEqual({
+      result: { base64: "AP8B" },
+    });
+  });
+
+  test("should accept MessagePack bodies", async () => {
+    // ["SET", "msgpack:key", bin(00 ff 01)]
+    const key = new TextEncoder().encode("msgpack:key");
+    const body = new Uint8Array([
+      0x93, 0xa3, ...new TextEncoder().encode("SET"),
+      0xa0 | key.length, ...key,
+      0xc4, binary.length, ...binary,
+    ]);
+    const result = await send("/pipeline", new Uint8Array([0x91, ...body]), {
+      "Content-Type": "application/msgpack",
+    });
+    expect(result.contentType).toBe("application/msgpack");
+    // [{"result": "OK"}]
+    expect([...result.body]).toEqual([
+      0x91, 0x81, 0xa6, ...new TextEncoder().encode("result"), 0xa2, 0x4f, 0x4b,
+    ]);
+  });
+
+  test("should reject a malformed body", async () => {
+    const result = await send("/", new Uint8Array([0xc1]), {
+      "Content-Type": "application/msgpack",
+    });
+    expect(result.status).toBe(400);
+    expect(result.contentType).toBe("application/msgpack");
+  });
+
+  test("should encode errors in the negotiated format", async () => {
+    const result = await send("/", cbor(["GET", "cbor:key"]), {
+      "Content-Type": "application/cbor",
+      Authorization: "Bearer not-a-token",
+    });
+    expect(result.status).toBe(401);
+    expect(result.contentType).toBe("application/cbor");
+    // {"error": "Invalid token"}
+    expect([...result.body]).toEqual([0xa1, ...cbor("error"), ...cbor("Invalid token")]);
+  });
+});
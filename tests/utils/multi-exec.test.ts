@@ -0,0 +1,85 @@
+import { test, expect, describe, afterEach } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+async function multiExec(commands: unknown) {
+  const response = await fetch(`${BASE_URL}/multi-exec`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(commands),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe("MULTI/EXEC", () => {
+  afterEach(async () => {
+    await multiExec([["DEL", "tx:counter", "tx:string"]]);
+  });
+
+  test("should return one result per command", async () => {
+    const { status, body } = await multiExec([
+      ["SET", "tx:counter", "1"],
+      ["INCR", "tx:counter"],
+      ["GET", "tx:counter"],
+    ]);
+    expect(status).toBe(200);
+    expect(body).toEqual([{ result: "OK" }, { result: 2 }, { result: "2" }]);
+  });
+
+  test("should report runtime errors per command and run the others", async () => {
+    const { status, body } = await multiExec([
+      ["SET", "tx:string", "not a number"],
+      ["INCR", "tx:string"],
+      ["GET", "tx:string"],
+    ]);
+    expect(status).toBe(200);
+    expect(body[0]).toEqual({ result: "OK" });
+    expect(body[1].error).toContain("ERR");
+    expect(body[2]).toEqual({ result: "not a number" });
+  });
+
+  test("should abort the whole transaction when a command cannot be queued", async () => {
+    const { status, body } = await multiExec([
+      ["SET", "tx:string", "value"],
+      ["NOTACOMMAND", "tx:string"],
+    ]);
+    expect(status).toBe(400);
+    expect(body.error).toStartWith("EXECABORT");
+
+    const { body: check } = await multiExec([["EXISTS", "tx:string"]]);
+    expect(check).toEqual([{ result: 0 }]);
+  });
+
+  test("should watch keys and return the reads with the results", async () => {
+    await multiExec([["SET", "tx:counter", "10"]]);
+    const { status, body } = await multiExec({
+      watch: ["tx:counter"],
+      reads: [["GET", "tx:counter"]],
+      commands: [["INCRBY", "tx:counter", "5"]],
+    });
+    expect(status).toBe(200);
+    expect(body).toEqual({ reads: [{ result: "10" }], exec: [{ result: 15 }] });
+  });
+
+  test("should abort with a 409 when a watched key changes before EXEC", async () => {
+    await multiExec([["SET", "tx:counter", "10"]]);
+    // Any write to a watched key aborts the transaction, even one from the
+    // same connection, so the write in the reads makes the conflict certain
+    const { status, body } = await multiExec({
+      watch: ["tx:counter"],
+      reads: [["INCR", "tx:counter"]],
+      commands: [["INCRBY", "tx:counter", "5"]],
+    });
+    expect(status).toBe(409);
+    expect(body.error).toStartWith("WATCHABORTED");
+    expect(body.aborted).toBe(true);
+    expect(body.reads).toEqual([{ result: 11 }]);
+
+    const { body: check } = await multiExec([["GET", "tx:counter"]]);
+    expect(check).toEqual([{ result: "11" }]);
+  });
+
+  test("should reject an invalid watch list", async () => {
+    const { status } = await multiExec({ watch: [], commands: [["GET", "tx:counter"]] });
+    expect(status).toBe(400);
+  });
+});
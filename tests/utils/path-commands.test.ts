@@ -0,0 +1,60 @@
+import { test, expect, describe, afterEach } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+async function call(path: string, init?: RequestInit) {
+  const response = await fetch(`${BASE_URL}${path}`, {
+    ...init,
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+  });
+  return response.json();
+}
+
+describe("Path-style commands", () => {
+  afterEach(async () => {
+    await call("/del/path:simple/path%2Fencoded/path:body");
+  });
+
+  test("should run a command from path segments", async () => {
+    expect(await call("/set/path:simple/value")).toEqual({ result: "OK" });
+    expect(await call("/get/path:simple")).toEqual({ result: "value" });
+  });
+
+  test("should URL-decode each segment", async () => {
+    await call("/set/path%2Fencoded/hello%20world");
+    expect(await call("/get/path%2Fencoded")).toEqual({ result: "hello world" });
+  });
+
+  test("should append query string options", async () => {
+    await call("/set/path:simple/value?EX=100");
+    const ttl = await call("/ttl/path:simple");
+    expect(ttl.result).toBeGreaterThan(0);
+    expect(ttl.result).toBeLessThanOrEqual(100);
+  });
+
+  test("should use the POST body as the last argument", async () => {
+    await call("/set/path:body", { method: "POST", body: "from the body" });
+    expect(await call("/get/path:body")).toEqual({ result: "from the body" });
+  });
+
+  test("should store a binary POST body byte for byte", async () => {
+    const value = new Uint8Array([0x00, 0xff, 0xfe, 0x80, 0x0a]);
+    expect(await call("/set/path:body", { method: "POST", body: value })).toEqual({
+      result: "OK",
+    });
+    const { result } = await call("/get/path:body");
+    expect(Buffer.from(result.base64, "base64").equals(Buffer.from(value))).toBe(true);
+  });
+
+  test("should keep non UTF-8 bytes of path segments", async () => {
+    await call("/set/path:simple/%FF%00%FE");
+    const { result } = await call("/get/path:simple");
+    expect(Buffer.from(result.base64, "base64").equals(Buffer.from([0xff, 0x00, 0xfe]))).toBe(
+      true,
+    );
+  });
+
+  test("should reject requests without a valid token", async () => {
+    const response = await fetch(`${BASE_URL}/get/path:simple`);
+    expect(response.status).toBe(401);
+  });
+});
@@ -0,0 +1,53 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+// The pool status is read from the admin API
+const ADMIN_TOKEN = Bun.env.ADMIN_TOKEN;
+const ADMIN_URL = Bun.env.ADMIN_URL ?? `${BASE_URL}/admin`;
+
+async function poolStatus() {
+  const response = await fetch(`${ADMIN_URL}/status`, {
+    headers: { Authorization: `Bearer ${ADMIN_TOKEN}` },
+  });
+  return (await response.json()).result.redis.pool;
+}
+
+async function burst(count: number) {
+  const responses = await Promise.all(
+    Array.from({ length: count }, () =>
+      fetch(BASE_URL, {
+        method: "POST",
+        headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+        body: JSON.stringify(["PING"]),
+      }),
+    ),
+  );
+  return responses.map((response) => response.status);
+}
+
+describe.skipIf(!ADMIN_TOKEN)("Connection pool", () => {
+  test("should serve concurrent requests without opening more connections than its size", async () => {
+    const statuses = await burst(50);
+    expect(statuses.every((status) => status === 200)).toBe(true);
+
+    const pool = await poolStatus();
+    expect(pool.open).toBeGreaterThan(0);
+    expect(pool.open).toBeLessThanOrEqual(pool.size);
+  });
+
+  test("should keep its connections open between requests", async () => {
+    await burst(50);
+    const before = await poolStatus();
+    await burst(50);
+    const after = await poolStatus();
+    expect(after.open).toBe(before.open);
+  });
+
+  test("should release every connection once the requests are answered", async () => {
+    await burst(50);
+    const pool = await poolStatus();
+    expect(pool.in_use).toBe(0);
+    expect(pool.waiters).toBe(0);
+    expect(pool.idle).toBe(pool.open);
+  });
+});
@@ -0,0 +1,58 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless connected as an ACL user to a Redis with requirepass
+const AUTH_SERVER_URL = Bun.env.AUTH_SERVER_URL;
+// Port of that Redis and the binary, to check that a bad password stops startup
+const AUTH_REDIS_PORT = Bun.env.AUTH_REDIS_PORT;
+const STASHLESS_BIN = Bun.env.STASHLESS_BIN;
+
+async function command(args: string[]) {
+  const response = await fetch(`${AUTH_SERVER_URL}/`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(args),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe.skipIf(!AUTH_SERVER_URL)("Redis authentication", () => {
+  afterAll(async () => {
+    await command(["DEL", "auth:key"]);
+  });
+
+  test("should run commands as the configured ACL user", async () => {
+    expect(await command(["SET", "auth:key", "value"])).toEqual({
+      status: 200,
+      body: { result: "OK" },
+    });
+    expect(await command(["GET", "auth:key"])).toEqual({
+      status: 200,
+      body: { result: "value" },
+    });
+  });
+});
+
+describe.skipIf(!AUTH_REDIS_PORT || !STASHLESS_BIN)("Redis authentication failure", () => {
+  test("should exit with a clear error when Redis rejects the password", async () => {
+    const proc = Bun.spawn([STASHLESS_BIN!, "--boring"], {
+      env: {
+        SLASHLESS_REDIS_HOST: "localhost",
+        SLASHLESS_REDIS_PORT: AUTH_REDIS_PORT,
+        SLASHLESS_REDIS_USERNAME: "stashless",
+        SLASHLESS_REDIS_PASSWORD: "wrong-password",
+        SLASHLESS_TOKEN: DEFAULT_TOKEN,
+        SLASHLESS_PORT: "3099",
+      },
+      stdout: "pipe",
+      stderr: "pipe",
+    });
+    const timer = setTimeout(() => proc.kill(), 10000);
+    const exitCode = await proc.exited;
+    clearTimeout(timer);
+
+    const output = (await new Response(proc.stdout).text()) + (await new Response(proc.stderr).text());
+    expect(exitCode).toBe(1);
+    expect(output).toContain("Redis rejected authentication");
+  }, 15000);
+});
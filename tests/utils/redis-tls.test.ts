@@ -0,0 +1,66 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless connected to Redis over mutual TLS (see "TLS to Redis" in the README)
+const TLS_SERVER_URL = Bun.env.TLS_SERVER_URL;
+// Same, without the CA and with SLASHLESS_REDIS_TLS_INSECURE=true
+const TLS_INSECURE_SERVER_URL = Bun.env.TLS_INSECURE_SERVER_URL;
+
+async function post(
+  path: string,
+  body: unknown,
+  headers: Record<string, string> = {},
+  server = TLS_SERVER_URL,
+) {
+  const response = await fetch(`${server}${path}`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}`, ...headers },
+    body: JSON.stringify(body),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe.skipIf(!TLS_SERVER_URL)("Redis over TLS", () => {
+  afterAll(async () => {
+    await post("/", ["DEL", "tls:zset", "tls:key"]);
+  });
+
+  test("should run commands with the client certificate", async () => {
+    expect(await post("/", ["SET", "tls:key", "value"])).toEqual({
+      status: 200,
+      body: { result: "OK" },
+    });
+    expect(await post("/", ["GET", "tls:key"])).toEqual({
+      status: 200,
+      body: { result: "value" },
+    });
+  });
+
+  test("should run pipelines and transactions", async () => {
+    const pipeline = await post("/pipeline", [
+      ["SET", "tls:key", "1"],
+      ["INCR", "tls:key"],
+    ]);
+    expect(pipeline).toEqual({ status: 200, body: [{ result: "OK" }, { result: 2 }] });
+
+    const transaction = await post("/multi-exec", [["INCR", "tls:key"], ["GET", "tls:key"]]);
+    expect(transaction).toEqual({ status: 200, body: [{ result: 3 }, { result: "3" }] });
+  });
+
+  test("should run RESP3 requests with the client certificate", async () => {
+    await post("/", ["ZADD", "tls:zset", "2.5", "member"]);
+    const { status, body } = await post("/", ["ZSCORE", "tls:zset", "member"], {
+      "slashless-protocol": "resp3",
+    });
+    expect(status).toBe(200);
+    expect(body).toEqual({ result: 2.5 });
+  });
+});
+
+describe.skipIf(!TLS_INSECURE_SERVER_URL)("Redis over TLS without verification", () => {
+  test("should connect without the CA when verification is disabled", async () => {
+    const { status, body } = await post("/", ["PING"], {}, TLS_INSECURE_SERVER_URL);
+    expect(status).toBe(200);
+    expect(body).toEqual({ result: "PONG" });
+  });
+});
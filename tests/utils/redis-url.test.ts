@@ -0,0 +1,46 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless given a SLASHLESS_REDIS_URL selecting database 2 of the test Redis
+const DB_SERVER_URL = Bun.env.DB_SERVER_URL;
+// Stashless given a unix:// SLASHLESS_REDIS_URL
+const UNIX_SERVER_URL = Bun.env.UNIX_SERVER_URL;
+
+async function command(url: string, args: string[]) {
+  const response = await fetch(url, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(args),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe.skipIf(!DB_SERVER_URL)("Redis URL with a database", () => {
+  afterAll(async () => {
+    await command(DB_SERVER_URL!, ["DEL", "url:db"]);
+  });
+
+  test("should store keys in the selected database", async () => {
+    await command(DB_SERVER_URL!, ["SET", "url:db", "db2"]);
+
+    expect((await command(DB_SERVER_URL!, ["GET", "url:db"])).body).toEqual({ result: "db2" });
+    // The main server uses database 0 of the same Redis
+    expect((await command(BASE_URL, ["GET", "url:db"])).body).toEqual({ result: null });
+  });
+});
+
+describe.skipIf(!UNIX_SERVER_URL)("Redis URL with a unix socket", () => {
+  afterAll(async () => {
+    await command(UNIX_SERVER_URL!, ["DEL", "url:unix"]);
+  });
+
+  test("should run commands over the socket", async () => {
+    expect(await command(UNIX_SERVER_URL!, ["SET", "url:unix", "socket"])).toEqual({
+      status: 200,
+      body: { result: "OK" },
+    });
+    expect((await command(UNIX_SERVER_URL!, ["GET", "url:unix"])).body).toEqual({
+      result: "socket",
+    });
+  });
+});
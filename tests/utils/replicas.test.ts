@@ -0,0 +1,63 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless with SLASHLESS_REDIS_REPLICAS pointing at a standalone Redis that
+// stands in for a replica. Only that Redis holds "replica:marker" (value
+// "replica"), so replies show which node answered.
+const REPLICA_SERVER_URL = Bun.env.REPLICA_SERVER_URL;
+
+async function post(path: string, body: unknown, headers: Record<string, string> = {}) {
+  const response = await fetch(`${REPLICA_SERVER_URL}${path}`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}`, ...headers },
+    body: JSON.stringify(body),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe.skipIf(!REPLICA_SERVER_URL)("Read replicas", () => {
+  afterAll(async () => {
+    await post("/", ["DEL", "replica:written"]);
+  });
+
+  test("should send read-only commands to a replica", async () => {
+    expect((await post("/", ["GET", "replica:marker"])).body).toEqual({ result: "replica" });
+  });
+
+  test("should send reads to the primary with slashless-read-primary", async () => {
+    const { body } = await post("/", ["GET", "replica:marker"], { "slashless-read-primary": "true" });
+    expect(body).toEqual({ result: null });
+  });
+
+  test("should send writes to the primary", async () => {
+    await post("/", ["SET", "replica:written", "primary"]);
+
+    const response = await fetch(BASE_URL, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+      body: JSON.stringify(["GET", "replica:written"]),
+    });
+    expect(await response.json()).toEqual({ result: "primary" });
+  });
+
+  test("should send pipelines of reads to a replica", async () => {
+    const { body } = await post("/pipeline", [
+      ["GET", "replica:marker"],
+      ["EXISTS", "replica:marker"],
+    ]);
+    expect(body).toEqual([{ result: "replica" }, { result: 1 }]);
+  });
+
+  test("should send pipelines containing a write to the primary", async () => {
+    const { body } = await post("/pipeline", [
+      ["SET", "replica:written", "primary"],
+      ["GET", "replica:marker"],
+    ]);
+    expect(body).toEqual([{ result: "OK" }, { result: null }]);
+  });
+
+  test("should run transactions on the primary", async () => {
+    const { body } = await post("/multi-exec", [["GET", "replica:marker"]]);
+    expect(body).toEqual([{ result: null }]);
+  });
+});
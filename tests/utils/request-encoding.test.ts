@@ -0,0 +1,58 @@
+import { test, expect, describe, afterEach } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+const b64 = (s: string) => Buffer.from(s, "latin1").toString("base64");
+
+async function post(path: string, body: unknown) {
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${DEFAULT_TOKEN}`,
+      "Content-Type": "application/json",
+      "slashless-request-encoding": "base64",
+    },
+    body: JSON.stringify(body),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+describe("Base64 request arguments", () => {
+  afterEach(async () => {
+    await post("/", [b64("DEL"), b64("binary:key"), b64("binary:tx")]);
+  });
+
+  test("should decode arguments of a single command", async () => {
+    const set = await post("/", [b64("SET"), b64("binary:key"), b64("hello\n\t")]);
+    expect(set.body).toEqual({ result: "OK" });
+
+    const get = await post("/", [b64("GET"), b64("binary:key")]);
+    expect(get.body).toEqual({ result: "hello\n\t" });
+  });
+
+  test("should decode arguments of a pipeline", async () => {
+    const result = await post("/pipeline", [
+      [b64("SET"), b64("binary:key"), b64("value")],
+      [b64("STRLEN"), b64("binary:key")],
+    ]);
+    expect(result.body).toEqual([{ result: "OK" }, { result: 5 }]);
+  });
+
+  test("should decode arguments of a transaction", async () => {
+    const result = await post("/multi-exec", [
+      [b64("SET"), b64("binary:tx"), b64("ÿ\u0000")],
+      [b64("STRLEN"), b64("binary:tx")],
+    ]);
+    expect(result.body).toEqual([{ result: "OK" }, { result: 2 }]);
+  });
+
+  test("should keep numbers as they are", async () => {
+    await post("/", [b64("SET"), b64("binary:key"), 42]);
+    const get = await post("/", [b64("GET"), b64("binary:key")]);
+    expect(get.body).toEqual({ result: "42" });
+  });
+
+  test("should reject invalid base64", async () => {
+    const result = await post("/", ["GET", "not base64!"]);
+    expect(result.status).toBe(400);
+  });
+});
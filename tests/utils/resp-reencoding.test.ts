@@ -0,0 +1,76 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+async function raw(path: string, body: unknown, headers: Record<string, string> = {}) {
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${DEFAULT_TOKEN}`,
+      "Content-Type": "application/json",
+      Accept: "application/x-resp",
+      ...headers,
+    },
+    body: JSON.stringify(body),
+  });
+  return {
+    contentType: response.headers.get("content-type"),
+    body: await response.text(),
+  };
+}
+
+describe("RESP re-encoding", () => {
+  afterAll(async () => {
+    await raw("/", ["DEL", "resp:key", "resp:hash"]);
+  });
+
+  test("should return the RESP reply of a command", async () => {
+    const set = await raw("/", ["SET", "resp:key", "value"]);
+    expect(set.contentType).toBe("application/x-resp");
+    expect(set.body).toBe("+OK\r\n");
+
+    expect((await raw("/", ["GET", "resp:key"])).body).toBe("$5\r\nvalue\r\n");
+    expect((await raw("/", ["GET", "resp:missing"])).body).toBe("$-1\r\n");
+  });
+
+  test("should return error replies as RESP errors", async () => {
+    const result = await raw("/", ["INCR", "resp:key"]);
+    expect(result.body.startsWith("-ERR")).toBe(true);
+  });
+
+  test("should return one reply per pipelined command", async () => {
+    const result = await raw("/pipeline", [
+      ["SET", "resp:key", "1"],
+      ["INCR", "resp:key"],
+      ["HINCRBY", "resp:key", "field", "1"],
+    ]);
+    expect(result.body).toMatch(/^\+OK\r\n:2\r\n-WRONGTYPE [^\r]*\r\n$/);
+  });
+
+  test("should return the MULTI, QUEUED and EXEC replies of a transaction", async () => {
+    const result = await raw("/multi-exec", [
+      ["SET", "resp:key", "1"],
+      ["INCR", "resp:key"],
+    ]);
+    expect(result.body).toBe("+OK\r\n+QUEUED\r\n+QUEUED\r\n*2\r\n+OK\r\n:2\r\n");
+  });
+
+  test("should keep RESP3 types with the RESP3 protocol", async () => {
+    await raw("/", ["HSET", "resp:hash", "field", "value"]);
+    const result = await raw("/", ["HGETALL", "resp:hash"], { "slashless-protocol": "resp3" });
+    expect(result.body).toBe("%1\r\n$5\r\nfield\r\n$5\r\nvalue\r\n");
+  });
+
+  test("should re-encode RESP3 doubles, big numbers and verbatim strings", async () => {
+    const resp3 = { "slashless-protocol": "resp3" };
+    const evalResp3 = (body: string) => raw("/", ["EVAL", `redis.setresp(3); ${body}`, "0"], resp3);
+
+    expect((await evalResp3("return {double=1.5}")).body).toBe(",1.5\r\n");
+    expect((await evalResp3("return {double=-2}")).body).toBe(",-2\r\n");
+    expect(
+      (await evalResp3("return {big_number='1234567890123456789012345678901234567890'}")).body,
+    ).toBe("(1234567890123456789012345678901234567890\r\n");
+    expect(
+      (await evalResp3("return {verbatim_string={format='txt', string='hello'}}")).body,
+    ).toBe("=9\r\ntxt:hello\r\n");
+  });
+});
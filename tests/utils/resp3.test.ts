@@ -0,0 +1,100 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+async function post(path: string, body: unknown, resp3 = true, base64 = false) {
+  const headers: Record<string, string> = {
+    Authorization: `Bearer ${DEFAULT_TOKEN}`,
+    "Content-Type": "application/json",
+  };
+  if (resp3) {
+    headers["slashless-protocol"] = "resp3";
+  }
+  if (base64) {
+    headers["upstash-encoding"] = "base64";
+  }
+  const response = await fetch(`${BASE_URL}${path}`, {
+    method: "POST",
+    headers,
+    body: JSON.stringify(body),
+  });
+  return response.json();
+}
+
+describe("RESP3 responses", () => {
+  afterAll(async () => {
+    await post("/", ["DEL", "resp3:hash", "resp3:zset", "resp3:set"]);
+  });
+
+  test("should return maps as JSON objects with base64 encoding", async () => {
+    await post("/", ["HSET", "resp3:hash", "a", "1", "b", "2"]);
+    expect(await post("/", ["HGETALL", "resp3:hash"], true, true)).toEqual({
+      result: { [btoa("a")]: btoa("1"), [btoa("b")]: btoa("2") },
+    });
+  });
+
+  test("should return maps as JSON objects with the default encoding", async () => {
+    await post("/", ["HSET", "resp3:hash", "a", "1", "b", "2"]);
+    expect(await post("/", ["HGETALL", "resp3:hash"])).toEqual({
+      result: { a: "1", b: "2" },
+    });
+  });
+
+  test("should escape map keys that look like tagged values", async () => {
+    await post("/", ["HSET", "resp3:hash", "a", "1", "base64", "x", "base64:y", "z"]);
+    const { result } = await post("/", ["HGETALL", "resp3:hash"]);
+    expect(result).toEqual({
+      a: "1",
+      b: "2",
+      [`base64:${btoa("base64")}`]: "x",
+      [`base64:${btoa("base64:y")}`]: "z",
+    });
+    await post("/", ["HDEL", "resp3:hash", "base64", "base64:y"]);
+  });
+
+  test("should write binary map keys as base64", async () => {
+    await fetch(BASE_URL, {
+      method: "POST",
+      headers: {
+        Authorization: `Bearer ${DEFAULT_TOKEN}`,
+        "slashless-request-encoding": "base64",
+      },
+      body: JSON.stringify([btoa("HSET"), btoa("resp3:binary"), "AP8B", btoa("value")]),
+    });
+    const { result } = await post("/", ["HGETALL", "resp3:binary"]);
+    expect(result).toEqual({ "base64:AP8B": "value" });
+    await post("/", ["DEL", "resp3:binary"]);
+  });
+
+  test("should keep the RESP2 shape without the header", async () => {
+    await post("/", ["HSET", "resp3:hash", "a", "1"]);
+    const result = await post("/", ["HGETALL", "resp3:hash"], false);
+    expect(Array.isArray(result.result)).toBe(true);
+  });
+
+  test("should return doubles as numbers", async () => {
+    await post("/", ["ZADD", "resp3:zset", "1.5", "member"]);
+    expect(await post("/", ["ZSCORE", "resp3:zset", "member"])).toEqual({ result: 1.5 });
+    expect(await post("/", ["ZSCORE", "resp3:zset", "member"], false)).toEqual({
+      result: "1.5",
+    });
+  });
+
+  test("should return booleans as JSON booleans", async () => {
+    const result = await post("/", ["EVAL", "redis.setresp(3); return true", "0"]);
+    expect(result).toEqual({ result: true });
+  });
+
+  test("should apply to pipelines and transactions", async () => {
+    await post("/", ["SADD", "resp3:set", "x"]);
+    const commands = [
+      ["HGETALL", "resp3:hash"],
+      ["ZSCORE", "resp3:zset", "member"],
+    ];
+    for (const path of ["/pipeline", "/multi-exec"]) {
+      const [hash, score] = await post(path, commands, true, true);
+      expect(typeof hash.result).toBe("object");
+      expect(Array.isArray(hash.result)).toBe(false);
+      expect(score.result).toBe(1.5);
+    }
+  });
+});
@@ -0,0 +1,82 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+const SCRIPT = "return 'scripts-test:' .. ARGV[1]";
+
+async function command(args: string[]) {
+  const response = await fetch(BASE_URL, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(args),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+async function scriptLoaded(sha: string) {
+  const { body } = await command(["SCRIPT", "EXISTS", sha]);
+  return body.result[0] === 1;
+}
+
+describe("Lua script cache", () => {
+  afterAll(async () => {
+    await command(["SCRIPT", "FLUSH"]);
+  });
+
+  test("should list the scripts sent with SCRIPT LOAD", async () => {
+    const { body } = await command(["SCRIPT", "LOAD", SCRIPT]);
+    const sha = body.result;
+
+    const response = await fetch(`${BASE_URL}/scripts`, {
+      headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    });
+    expect(response.status).toBe(200);
+    expect((await response.json()).result).toContain(sha);
+  });
+
+  test("should run EVALSHA of a known script after Redis lost it", async () => {
+    const { body } = await command(["SCRIPT", "LOAD", SCRIPT]);
+    const sha = body.result;
+    await command(["SCRIPT", "FLUSH"]);
+    expect(await scriptLoaded(sha)).toBe(false);
+
+    const { status, body: result } = await command(["EVALSHA", sha, "0", "one"]);
+    expect(status).toBe(200);
+    expect(result).toEqual({ result: "scripts-test:one" });
+  });
+
+  test("should load the known scripts again after a NOSCRIPT error", async () => {
+    const { body } = await command(["SCRIPT", "LOAD", SCRIPT]);
+    const sha = body.result;
+    await command(["SCRIPT", "FLUSH"]);
+
+    await command(["EVALSHA", sha, "0", "two"]);
+
+    // The reload runs in the background
+    let loaded = false;
+    for (let i = 0; i < 20 && !loaded; i++) {
+      await Bun.sleep(50);
+      loaded = await scriptLoaded(sha);
+    }
+    expect(loaded).toBe(true);
+  });
+
+  test("should send EVALSHA of a known script as EVAL in a pipeline", async () => {
+    const { body } = await command(["SCRIPT", "LOAD", SCRIPT]);
+    const sha = body.result;
+    await command(["SCRIPT", "FLUSH"]);
+
+    const response = await fetch(`${BASE_URL}/pipeline`, {
+      method: "POST",
+      headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+      body: JSON.stringify([
+        ["EVALSHA", sha, "0", "three"],
+        ["PING"],
+      ]),
+    });
+    expect(response.status).toBe(200);
+    expect(await response.json()).toEqual([
+      { result: "scripts-test:three" },
+      { result: "PONG" },
+    ]);
+  });
+});
@@ -0,0 +1,105 @@
+import { test, expect, describe, afterAll } from "bun:test";
+import { createConnection } from "node:net";
+import { DEFAULT_TOKEN } from "../helpers/client";
+
+// Stashless in Sentinel mode, and the Sentinel it asks for the master of
+// "mymaster" (a master with one replica, separate from the main test Redis)
+const SENTINEL_SERVER_URL = Bun.env.SENTINEL_SERVER_URL;
+const SENTINEL_ADDRESS = Bun.env.SENTINEL_ADDRESS;
+const STASHLESS_BIN = Bun.env.STASHLESS_BIN;
+
+async function command(args: string[]) {
+  const response = await fetch(SENTINEL_SERVER_URL!, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${DEFAULT_TOKEN}` },
+    body: JSON.stringify(args),
+  });
+  return { status: response.status, body: await response.json() };
+}
+
+/** Send a command to a Redis or Sentinel node directly and return its raw reply */
+function raw(address: string, args: string[]): Promise<string> {
+  const [host, port] = address.split(":");
+  const request = `*${args.length}\r\n${args.map((arg) => `$${arg.length}\r\n${arg}\r\n`).join("")}`;
+  return new Promise((resolve, reject) => {
+    const socket = createConnection({ host, port: Number(port) }, () => socket.write(request));
+    socket.once("data", (data) => {
+      socket.end();
+      resolve(data.toString());
+    });
+    socket.on("error", reject);
+  });
+}
+
+const sentinel = (args: string[]) => raw(SENTINEL_ADDRESS!, args);
+
+/** Address of the current master, as `host:port` */
+async function masterAddress() {
+  const lines = (await sentinel(["SENTINEL", "GET-MASTER-ADDR-BY-NAME", "mymaster"])).split("\r\n");
+  return `${lines[2]}:${lines[4]}`;
+}
+
+describe.skipIf(!SENTINEL_SERVER_URL || !SENTINEL_ADDRESS)("Redis Sentinel", () => {
+  afterAll(async () => {
+    await command(["DEL", "sentinel:key"]);
+  });
+
+  test("should run commands on the master found by Sentinel", async () => {
+    expect(await command(["SET", "sentinel:key", "value"])).toEqual({
+      status: 200,
+      body: { result: "OK" },
+    });
+    expect((await command(["GET", "sentinel:key"])).body).toEqual({ result: "value" });
+  });
+
+  test(
+    "should follow the master after a failover",
+    async () => {
+      const oldMaster = await masterAddress();
+
+      // Sentinel needs a few seconds to discover the replica
+      let reply = await sentinel(["SENTINEL", "FAILOVER", "mymaster"]);
+      for (let i = 0; i < 30 && !reply.startsWith("+OK"); i++) {
+        await Bun.sleep(1000);
+        reply = await sentinel(["SENTINEL", "FAILOVER", "mymaster"]);
+      }
+      expect(reply).toStartWith("+OK");
+
+      // Wait until the old master has become a replica, so a write can only
+      // succeed on the new master
+      let role = await raw(oldMaster, ["ROLE"]);
+      for (let i = 0; i < 30 && !role.includes("slave"); i++) {
+        await Bun.sleep(1000);
+        role = await raw(oldMaster, ["ROLE"]);
+      }
+      expect(role).toContain("slave");
+      expect(await masterAddress()).not.toBe(oldMaster);
+
+      let write = await command(["SET", "sentinel:key", "after failover"]);
+      for (let i = 0; i < 30 && write.status !== 200; i++) {
+        await Bun.sleep(1000);
+        write = await command(["SET", "sentinel:key", "after failover"]);
+      }
+      expect(write).toEqual({ status: 200, body: { result: "OK" } });
+      expect((await command(["GET", "sentinel:key"])).body).toEqual({ result: "after failover" });
+    },
+    100000,
+  );
+});
+
+describe.skipIf(!STASHLESS_BIN)("Redis Sentinel configuration", () => {
+  test("should refuse a Sentinel list without any address", async () => {
+    const proc = Bun.spawn([STASHLESS_BIN!, "--boring"], {
+      env: { SLASHLESS_REDIS_SENTINELS: ",", SLASHLESS_TOKEN: DEFAULT_TOKEN, SLASHLESS_PORT: "3098" },
+      stdout: "pipe",
+      stderr: "pipe",
+    });
+    const timer = setTimeout(() => proc.kill(), 10000);
+    const exitCode = await proc.exited;
+    clearTimeout(timer);
+
+    const output = (await new Response(proc.stdout).text()) + (await new Response(proc.stderr).text());
+    expect(exitCode).toBe(1);
+    expect(output).toContain("SLASHLESS_REDIS_SENTINELS must list at least one host:port");
+  }, 15000);
+});
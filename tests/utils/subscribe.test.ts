@@ -0,0 +1,73 @@
+import { test, expect, describe } from "bun:test";
+import { BASE_URL, DEFAULT_TOKEN } from "../helpers/client";
+
+const headers = { Authorization: `Bearer ${DEFAULT_TOKEN}` };
+
+/** Read SSE `data:` lines until `count` events were received */
+async function readEvents(response: Response, count: number): Promise<string[]> {
+  const reader = response.body!.getReader();
+  const decoder = new TextDecoder();
+  const events: string[] = [];
+  let buffer = "";
+
+  while (events.length < count) {
+    const { value, done } = await reader.read();
+    if (done) break;
+    buffer += decoder.decode(value, { stream: true });
+    const lines = buffer.split("\n");
+    buffer = lines.pop() ?? "";
+    for (const line of lines) {
+      if (line.startsWith("data:")) events.push(line.slice(5).trim());
+    }
+  }
+  await reader.cancel();
+  return events;
+}
+
+async function publish(channel: string, message: string) {
+  await fetch(`${BASE_URL}/publish/${channel}/${message}`, { headers });
+}
+
+describe("SSE subscriptions", () => {
+  test("should stream messages published on a channel", async () => {
+    const response = await fetch(`${BASE_URL}/subscribe/sse:news`, { headers });
+    expect(response.headers.get("content-type")).toContain("text/event-stream");
+
+    const events = readEvents(response, 3);
+    await Bun.sleep(200);
+    await publish("sse:news", "hello");
+    await publish("sse:news", "world");
+
+    expect(await events).toEqual([
+      "subscribe,sse:news,1",
+      "message,sse:news,hello",
+      "message,sse:news,world",
+    ]);
+  });
+
+  test("should fan out to several subscribers of the same channel", async () => {
+    const first = fetch(`${BASE_URL}/subscribe/sse:shared`, { headers });
+    const second = fetch(`${BASE_URL}/subscribe/sse:shared`, { headers });
+    const events = Promise.all([readEvents(await first, 2), readEvents(await second, 2)]);
+    await Bun.sleep(200);
+    await publish("sse:shared", "fanout");
+
+    for (const received of await events) {
+      expect(received).toEqual(["subscribe,sse:shared,1", "message,sse:shared,fanout"]);
+    }
+  });
+
+  test("should stream messages matching a pattern", async () => {
+    const response = await fetch(`${BASE_URL}/psubscribe/sse:room:*`, { headers });
+    const events = readEvents(response, 2);
+    await Bun.sleep(200);
+    await publish("sse:room:1", "hi");
+
+    expect(await events).toEqual(["psubscribe,sse:room:*,1", "pmessage,sse:room:*,sse:room:1,hi"]);
+  });
+
+  test("should reject subscriptions without a valid token", async () => {
+    const response = await fetch(`${BASE_URL}/subscribe/sse:news`);
+    expect(response.status).toBe(401);
+  });
+});